@@ -1,13 +1,82 @@
-use crate::state_repr::{GameElements, GameState, SpecialSkill};
+use strum_macros::{EnumCount as EnumCountMacro, EnumIter};
+
+use crate::skill::get_sp_skill_targets;
+use crate::state_repr::{is_passable_elem, GameElements, GameState};
+use crate::terminal::is_terminal_state;
+
+/// Direction on the board.
+/// The grid of the rules tells which ones exist and where they lead (see `Topology`)
+#[derive(Debug, PartialEq, Clone, Copy, EnumCountMacro, EnumIter)]
+pub enum Direction {
+    Up,
+    Down,
+    Right,
+    Left,
+    /// Octile and hex grids only
+    UpRight,
+    UpLeft,
+    DownRight,
+    DownLeft,
+}
+
+/// Where a special skill is aimed
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SkillTarget {
+    /// Skills that need no aim (ReduceEnemiesATK, Swap)
+    None,
+    /// ClearRoadTile and Dash
+    Direction(Direction),
+    /// Teleport and PlaceWall, (x, y)
+    Coordinate(usize, usize),
+}
 
 /// Action available for action
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Action {
     Up,
     Down,
     Right,
     Left,
-    SpSkill,
+    /// Diagonal moves, octile and hex grids only
+    UpRight,
+    UpLeft,
+    DownRight,
+    DownLeft,
+    /// Use the special skill of an inventory slot
+    SpSkill(usize, SkillTarget),
+    /// Attack the adjacent enemy in the direction (combat mode only)
+    Attack(Direction),
+}
+
+impl Action {
+    /// Direction of a move, None for skills and attacks
+    pub fn get_move_direction(&self) -> Option<Direction> {
+        match self {
+            Action::Up => Some(Direction::Up),
+            Action::Down => Some(Direction::Down),
+            Action::Right => Some(Direction::Right),
+            Action::Left => Some(Direction::Left),
+            Action::UpRight => Some(Direction::UpRight),
+            Action::UpLeft => Some(Direction::UpLeft),
+            Action::DownRight => Some(Direction::DownRight),
+            Action::DownLeft => Some(Direction::DownLeft),
+            _ => None,
+        }
+    }
+
+    /// Move in the direction
+    pub fn from_move_direction(direction: Direction) -> Action {
+        match direction {
+            Direction::Up => Action::Up,
+            Direction::Down => Action::Down,
+            Direction::Right => Action::Right,
+            Direction::Left => Action::Left,
+            Direction::UpRight => Action::UpRight,
+            Direction::UpLeft => Action::UpLeft,
+            Direction::DownRight => Action::DownRight,
+            Direction::DownLeft => Action::DownLeft,
+        }
+    }
 }
 
 /// Get possible action that can be used given the state
@@ -16,72 +85,130 @@ pub enum Action {
 /// # Parameters
 ///
 /// * state: the state of the game
-/// * player: Player, 0 or 1
+/// * player: Player id
 ///
 /// # Returns
 /// Return possible action in Vec<Action>
 pub fn get_legal_action(state: &GameState, player: u8) -> Vec<Action> {
-    let mut legal_vecs: Vec<Action> = Vec::new();
-    // contains player 0, player 1
-    // coordinates x_player, y_player
-    let (x_p, y_p): (i8, i8) = if player == 0 {
-        let pos = state.get_player_position().0;
-        (pos.0 as i8, pos.1 as i8)
-    } else {
-        let pos = state.get_player_position().1;
-        (pos.0 as i8, pos.1 as i8)
+    // a trapped player waits, unless no other player can play
+    let other_can_play = || {
+        state.get_active_players().into_iter().any(|other| {
+            other != player
+                && state.get_player_wait(other) == 0
+                && !get_available_action(state, other).is_empty()
+        })
     };
+    if state.get_player_wait(player) > 0 && other_can_play() {
+        return Vec::new();
+    }
+    get_available_action(state, player)
+}
 
-    let current_sp_skill = state.get_player_sp(player);
+/// Actions of the player, traps ignored. Empty for a player out of the game.
+pub fn get_available_action(state: &GameState, player: u8) -> Vec<Action> {
+    let mut legal_vecs: Vec<Action> = Vec::new();
+    if state.is_player_out(player) {
+        return legal_vecs;
+    }
+    let own = state.get_player_coor(player);
     let maprepr = state.get_map_representation();
-    let len_map = maprepr.len() as i8;
 
     // validate first x_player, y_player
-    // since the size is casted from usize, impossible to get < 0>
-    if x_p >= len_map || y_p >= len_map {
+    if own.0 >= maprepr.len() || own.1 >= maprepr.len() {
         panic!("Invalid player position");
     }
 
-    // Validate Up
-    if validate_coor_move(x_p, y_p - 1, maprepr) {
-        legal_vecs.push(Action::Up);
+    // one move per direction of the grid
+    for (direction, coor) in state.get_topology().get_neighbours(own) {
+        if validate_coor_move(coor, maprepr) {
+            legal_vecs.push(Action::from_move_direction(direction));
+        }
     }
 
-    // Validate Down
-    if validate_coor_move(x_p, y_p + 1, maprepr) {
-        legal_vecs.push(Action::Down);
-    }
+    // combat mode
+    let attacks = get_attack_directions(state, player).into_iter();
+    legal_vecs.extend(attacks.map(Action::Attack));
 
-    // Validate Right
-    if validate_coor_move(x_p + 1, y_p, maprepr) {
-        legal_vecs.push(Action::Right);
+    // one action per valid target of each held skill,
+    // a skill held twice is only offered from its first slot
+    let inventory = state.get_player_inventory(player);
+    for (slot, skill) in inventory.iter().enumerate() {
+        if inventory[..slot].contains(skill) {
+            continue;
+        }
+        let targets = get_sp_skill_targets(state, player, skill).into_iter();
+        legal_vecs.extend(targets.map(|target| Action::SpSkill(slot, target)));
     }
 
-    // Validate Left
-    if validate_coor_move(x_p - 1, y_p, maprepr) {
-        legal_vecs.push(Action::Left);
+    legal_vecs
+}
+
+/// Player that moves after `mover` played, players take turns in id order.
+/// A player without legal action is skipped, like on the CLI.
+///
+/// # Parameters
+///
+/// * state: the state after the move
+/// * mover: Player that just played
+///
+pub fn get_next_player_to_move(state: &GameState, mover: u8) -> u8 {
+    let player_count = state.get_player_count() as u8;
+    let following = (mover + 1) % player_count;
+    if is_terminal_state(state) {
+        return following;
     }
+    (1..=player_count)
+        .map(|offset| (mover + offset) % player_count)
+        .find(|player| !get_legal_action(state, *player).is_empty())
+        .unwrap_or(following)
+}
 
-    if !matches!(current_sp_skill, SpecialSkill::None) {
-        legal_vecs.push(Action::SpSkill);
+/// Players acting in the next round of the simultaneous mode:
+/// every player with a legal action, in id order
+pub fn get_players_to_act(state: &GameState) -> Vec<u8> {
+    if is_terminal_state(state) {
+        return Vec::new();
     }
+    state
+        .get_active_players()
+        .into_iter()
+        .filter(|player| !get_legal_action(state, *player).is_empty())
+        .collect()
+}
 
-    return legal_vecs;
+/// Player that starts the game: player 1, unless stuck from the beginning
+pub fn get_first_player_to_move(state: &GameState) -> u8 {
+    let last_player = state.get_player_count() as u8 - 1;
+    get_next_player_to_move(state, last_player)
+}
+
+/// Directions where an enemy stands next to the player, empty if combat is off
+pub fn get_attack_directions(state: &GameState, player: u8) -> Vec<Direction> {
+    if !state.get_rules().combat.enabled {
+        return Vec::new();
+    }
+    let own = state.get_player_coor(player);
+    let enemies = state.get_enemies(player);
+    state
+        .get_topology()
+        .get_neighbours(own)
+        .into_iter()
+        .filter(|(_, target)| {
+            enemies
+                .iter()
+                .any(|enemy| state.get_player_coor(*enemy) == *target)
+        })
+        .map(|(direction, _)| direction)
+        .collect()
 }
 
 /// validate action after move (lazy operation)
 ///
 /// # Parameters
 /// 
-/// * x: x after move
-/// * y: y after move
-/// * map_repr: map representation (non mutable). Must be square area
+/// * coor: x, y after move, inside the board (see `Topology::get_neighbour`)
+/// * map_repr: map representation (non mutable)
 ///
-fn validate_coor_move(x: i8, y: i8, map_repr: &Vec<Vec<GameElements>>) -> bool {
-    let valid = x >= 0
-    && y >= 0
-    && (x as usize) < (map_repr.len() as usize)
-    && (y as usize) < (map_repr.len() as usize)
-    && map_repr[x as usize][y as usize] != GameElements::NonPassable;
-    valid
+fn validate_coor_move(coor: (usize, usize), map_repr: &[Vec<GameElements>]) -> bool {
+    is_passable_elem(&map_repr[coor.0][coor.1])
 }
@@ -0,0 +1,73 @@
+//! Agent Module
+//!
+//! Anything that can choose an action for a player: search bots,
+//! learned agents, random baseline.
+//!
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::action::{get_legal_action, Action};
+use crate::fog::PlayerView;
+use crate::state_repr::GameState;
+
+/// Agent playing the game
+pub trait Agent {
+    /// Choose an action for the player
+    ///
+    /// # Parameters
+    ///
+    /// * state: the state of the game
+    /// * player: Player to move
+    ///
+    /// # Returns
+    ///
+    /// One of `get_legal_action`, None if there is no legal action
+    fn select_action(&mut self, state: &GameState, player: u8) -> Option<Action>;
+
+    /// Choose an action in fog mode, from what the player knows.
+    /// By default the agent plays on one state drawn from the view.
+    ///
+    /// # Parameters
+    ///
+    /// * view: what the player knows about the game
+    ///
+    /// # Returns
+    ///
+    /// One of `view.legal_actions`, None if there is no legal action
+    fn select_action_from_view(&mut self, view: &PlayerView) -> Option<Action> {
+        let mut rng = StdRng::seed_from_u64(view.turn_count as u64);
+        let state = view.determinize(&mut rng);
+        self.select_action(&state, view.player)
+            .filter(|action| view.legal_actions.contains(action))
+            .or_else(|| view.legal_actions.first().copied())
+    }
+
+    /// Name shown on the CLI
+    fn get_name(&self) -> String;
+}
+
+/// Play a uniformly random legal action
+pub struct RandomAgent {
+    rng: StdRng,
+}
+
+impl RandomAgent {
+    pub fn new(seed: u64) -> RandomAgent {
+        RandomAgent {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn select_action(&mut self, state: &GameState, player: u8) -> Option<Action> {
+        get_legal_action(state, player)
+            .choose(&mut self.rng)
+            .copied()
+    }
+
+    fn get_name(&self) -> String {
+        "Random".to_string()
+    }
+}
@@ -0,0 +1,3 @@
+pub mod base;
+pub mod q_learning;
+pub mod search_agent;
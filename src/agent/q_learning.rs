@@ -0,0 +1,328 @@
+//! Q-Learning Agent Module
+//!
+//! Tabular Q-learning trained by self-play, meant for small boards.
+//!
+//! One table is shared by both players: the key contains the player to move,
+//! and values are always seen from that player. As the game is zero-sum,
+//! the target of a move followed by an opponent turn is the negated best
+//! value of the opponent.
+//! In simultaneous mode every player of a round is trained on the state after
+//! the round, with its own best value in the next round as the target.
+//!
+//! # File format
+//!
+//! A header line `# size N inventory M` with the board and inventory sizes of
+//! the rules it was trained on, then one state per line: `key<TAB>q_0,q_1,...`
+//! with one value per entry of the action space of the rules (`get_action_space`).
+//!
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::action::{
+    get_first_player_to_move, get_legal_action, get_next_player_to_move, get_players_to_act, Action,
+};
+use crate::agent::base::Agent;
+use crate::environment::{action_to_index, get_action_space_size};
+use crate::result::result_function::{result_after_action, result_after_round};
+use crate::rules::RuleSet;
+use crate::state_repr::{GameState, MapRandom};
+use crate::terminal::{get_result_for_player, is_terminal_state};
+
+/// Settings of the training
+#[derive(Debug, Clone)]
+pub struct QLearningConfig {
+    pub episodes: usize,
+    pub rules: RuleSet,
+    /// Number of different maps trained on (map seeds `seed..seed + map_pool`)
+    pub map_pool: u64,
+    pub learning_rate: f32,
+    pub discount: f32,
+    /// Chance of playing a random action while training
+    pub epsilon: f32,
+    pub seed: u64,
+}
+
+impl Default for QLearningConfig {
+    fn default() -> Self {
+        QLearningConfig {
+            episodes: 50_000,
+            rules: RuleSet::default(),
+            map_pool: 20,
+            learning_rate: 0.2,
+            discount: 0.95,
+            epsilon: 0.2,
+            seed: 0,
+        }
+    }
+}
+
+/// Learned values of (state, action)
+#[derive(Debug, Clone, Default)]
+pub struct QTable {
+    values: HashMap<String, Vec<f32>>,
+}
+
+/// Header line of a table file, the action space depends on these sizes
+fn get_header(rules: &RuleSet) -> String {
+    format!(
+        "# size {} inventory {}",
+        rules.board.size, rules.skills.inventory_size
+    )
+}
+
+/// Key of a state for the player to move
+fn get_q_key(state: &GameState, player: u8) -> String {
+    format!("{}|{}", player, state.get_state_key())
+}
+
+impl QTable {
+    pub fn new() -> QTable {
+        QTable::default()
+    }
+
+    /// Number of states in the table
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Value of an action, 0 if never seen
+    pub fn get_value(&self, state: &GameState, player: u8, action: &Action) -> f32 {
+        self.values
+            .get(&get_q_key(state, player))
+            .and_then(|values| values.get(action_to_index(action, state.get_rules())))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Best legal action and its value, None if there is no legal action
+    pub fn get_best_action(&self, state: &GameState, player: u8) -> Option<(Action, f32)> {
+        get_legal_action(state, player)
+            .into_iter()
+            .map(|action| (action, self.get_value(state, player, &action)))
+            .reduce(|best, other| if other.1 > best.1 { other } else { best })
+    }
+
+    /// Whether the state was visited while training
+    pub fn contains_state(&self, state: &GameState, player: u8) -> bool {
+        self.values.contains_key(&get_q_key(state, player))
+    }
+
+    fn update_value(
+        &mut self,
+        state: &GameState,
+        player: u8,
+        action: &Action,
+        target: f32,
+        learning_rate: f32,
+    ) {
+        let values = self
+            .values
+            .entry(get_q_key(state, player))
+            .or_insert_with(|| vec![0.0; get_action_space_size(state.get_rules())]);
+        let value = &mut values[action_to_index(action, state.get_rules())];
+        *value += learning_rate * (target - *value);
+    }
+
+    /// Write the table trained on the rules to a file
+    pub fn save(&self, path: &Path, rules: &RuleSet) -> io::Result<()> {
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        writeln!(writer, "{}", get_header(rules))?;
+        for (key, values) in &self.values {
+            let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            writeln!(writer, "{}\t{}", key, values.join(","))?;
+        }
+        writer.flush()
+    }
+
+    ///
+    /// Read a table written by `save`
+    ///
+    /// # Parameters
+    ///
+    /// * path: File of the table
+    /// * rules: Rules the table is played with, every row must cover their action space
+    ///
+    pub fn load(path: &Path, rules: &RuleSet) -> io::Result<QTable> {
+        let invalid = |line_no: usize| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid Q-table line {}", line_no + 1),
+            )
+        };
+        let action_space_size = get_action_space_size(rules);
+        let mut values = HashMap::new();
+        for (line_no, line) in fs::read_to_string(path)?.lines().enumerate() {
+            if line.starts_with('#') {
+                if line != get_header(rules) {
+                    let trained_size = line.split_whitespace().nth(2).unwrap_or("?");
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "Q-table trained with `{}`, the rules have `{}` (play it with --size {})",
+                            line.trim_start_matches("# "),
+                            get_header(rules).trim_start_matches("# "),
+                            trained_size
+                        ),
+                    ));
+                }
+                continue;
+            }
+            let (key, raw_values) = line.split_once('\t').ok_or_else(|| invalid(line_no))?;
+            let parsed = raw_values
+                .split(',')
+                .map(|v| v.parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|_| invalid(line_no))?;
+            if parsed.len() != action_space_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Q-table line {} has {} actions, the rules have {} (table of other rules?)",
+                        line_no + 1,
+                        parsed.len(),
+                        action_space_size
+                    ),
+                ));
+            }
+            values.insert(key.to_string(), parsed);
+        }
+        Ok(QTable { values })
+    }
+}
+
+/// Train a table by self-play
+///
+/// # Parameters
+///
+/// * config: Settings of the training
+///
+/// # Returns
+///
+/// The learned table
+pub fn train_q_table(config: &QLearningConfig) -> QTable {
+    let mut table = QTable::new();
+    let mut rng = StdRng::seed_from_u64(config.seed);
+
+    for episode in 0..config.episodes {
+        let map_seed = config.seed + episode as u64 % config.map_pool.max(1);
+        let mut state = GameState::new_with_rules(&config.rules, MapRandom::FullyRandom, map_seed);
+        let mut player = get_first_player_to_move(&state);
+
+        while !is_terminal_state(&state) {
+            if state.get_rules().turns.simultaneous {
+                let actions: Vec<(u8, Action)> = get_players_to_act(&state)
+                    .into_iter()
+                    .map(|player| {
+                        (
+                            player,
+                            choose_training_action(&table, &state, player, config, &mut rng),
+                        )
+                    })
+                    .collect();
+                if actions.is_empty() {
+                    break;
+                }
+                let next_state = result_after_round(&state, &actions);
+                for (player, action) in actions.iter() {
+                    let target = if is_terminal_state(&next_state) {
+                        f32::from(get_result_for_player(&next_state, *player))
+                    } else {
+                        let best_next = table
+                            .get_best_action(&next_state, *player)
+                            .map_or(0.0, |(_, value)| value);
+                        config.discount * best_next
+                    };
+                    table.update_value(&state, *player, action, target, config.learning_rate);
+                }
+                state = next_state;
+                continue;
+            }
+
+            let action = choose_training_action(&table, &state, player, config, &mut rng);
+            let next_state = result_after_action(&state, &action, player);
+            let next_player = get_next_player_to_move(&next_state, player);
+            let target = if is_terminal_state(&next_state) {
+                f32::from(get_result_for_player(&next_state, player))
+            } else {
+                let best_next = table
+                    .get_best_action(&next_state, next_player)
+                    .map_or(0.0, |(_, value)| value);
+                let best_next = if next_player == player {
+                    best_next
+                } else {
+                    -best_next
+                };
+                config.discount * best_next
+            };
+            table.update_value(&state, player, &action, target, config.learning_rate);
+
+            state = next_state;
+            player = next_player;
+        }
+    }
+    table
+}
+
+/// Random legal action with a chance of `epsilon`, the best action of the table otherwise
+fn choose_training_action(
+    table: &QTable,
+    state: &GameState,
+    player: u8,
+    config: &QLearningConfig,
+    rng: &mut StdRng,
+) -> Action {
+    if rng.gen::<f32>() < config.epsilon {
+        *get_legal_action(state, player)
+            .choose(rng)
+            .expect("Non terminal player always has an action")
+    } else {
+        table
+            .get_best_action(state, player)
+            .expect("Non terminal player always has an action")
+            .0
+    }
+}
+
+/// Play the best action of a learned table.
+/// Unknown states fall back to a random legal action.
+pub struct QLearningAgent {
+    table: QTable,
+    rng: StdRng,
+}
+
+impl QLearningAgent {
+    pub fn new(table: QTable, seed: u64) -> QLearningAgent {
+        QLearningAgent {
+            table,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Agent for QLearningAgent {
+    fn select_action(&mut self, state: &GameState, player: u8) -> Option<Action> {
+        if self.table.contains_state(state, player) {
+            self.table
+                .get_best_action(state, player)
+                .map(|(action, _)| action)
+        } else {
+            get_legal_action(state, player)
+                .choose(&mut self.rng)
+                .copied()
+        }
+    }
+
+    fn get_name(&self) -> String {
+        format!("Q-learning ({} states)", self.table.len())
+    }
+}
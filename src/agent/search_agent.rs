@@ -0,0 +1,79 @@
+//! Search Agent Module
+//!
+//! Agents backed by the search algorithms.
+//!
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::action::Action;
+use crate::agent::base::Agent;
+use crate::search::evaluation::EvalWeights;
+use crate::fog::PlayerView;
+use crate::search::mcts::{run_determinized_mcts, run_mcts, run_simultaneous_mcts, MctsConfig};
+use crate::search::minimax::{
+    analyze_legal_actions, analyze_simultaneous_actions, get_best_analysis,
+};
+use crate::state_repr::GameState;
+
+/// Play the best action of the minimax search
+/// (maximin over the rounds in simultaneous mode)
+pub struct MinimaxAgent {
+    depth: u8,
+    weights: EvalWeights,
+}
+
+impl MinimaxAgent {
+    pub fn new(depth: u8, weights: EvalWeights) -> MinimaxAgent {
+        MinimaxAgent { depth, weights }
+    }
+}
+
+impl Agent for MinimaxAgent {
+    fn select_action(&mut self, state: &GameState, player: u8) -> Option<Action> {
+        let analyses = if state.get_rules().turns.simultaneous {
+            analyze_simultaneous_actions(state, player, self.depth, &self.weights)
+        } else {
+            analyze_legal_actions(state, player, self.depth, &self.weights)
+        };
+        get_best_analysis(&analyses).map(|analysis| analysis.action)
+    }
+
+    fn get_name(&self) -> String {
+        format!("Minimax (depth {})", self.depth)
+    }
+}
+
+/// Play the most visited action of the MCTS
+/// (flat UCB over the own actions in simultaneous mode,
+/// summed over several states drawn from the view in fog mode)
+pub struct MctsAgent {
+    config: MctsConfig,
+    rng: StdRng,
+}
+
+impl MctsAgent {
+    pub fn new(config: MctsConfig, seed: u64) -> MctsAgent {
+        MctsAgent {
+            config,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Agent for MctsAgent {
+    fn select_action(&mut self, state: &GameState, player: u8) -> Option<Action> {
+        if state.get_rules().turns.simultaneous {
+            run_simultaneous_mcts(state, player, &self.config, &mut self.rng).best_action
+        } else {
+            run_mcts(state, player, &self.config, &mut self.rng).best_action
+        }
+    }
+
+    fn select_action_from_view(&mut self, view: &PlayerView) -> Option<Action> {
+        run_determinized_mcts(view, &self.config, &mut self.rng).best_action
+    }
+
+    fn get_name(&self) -> String {
+        format!("MCTS ({} simulations)", self.config.simulations)
+    }
+}
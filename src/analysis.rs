@@ -0,0 +1,158 @@
+//! Analysis Module
+//!
+//! Move hints, evaluation bar and the blunder report of a finished game.
+//! Everything here is built on top of the minimax search.
+//!
+use crate::action::Action;
+use crate::search::evaluation::{EvalWeights, WIN_SCORE};
+use crate::search::minimax::{
+    analyze_legal_actions, analyze_simultaneous_actions, get_best_analysis, ActionAnalysis,
+};
+use crate::state_repr::GameState;
+
+/// Width of the evaluation bar in characters
+const EVALUATION_BAR_WIDTH: usize = 20;
+
+/// Score loss (compared to the best move) for each move label
+const BLUNDER_THRESHOLD: i32 = 200;
+const MISTAKE_THRESHOLD: i32 = 60;
+const INACCURACY_THRESHOLD: i32 = 20;
+
+/// A played move compared to the best move of the position
+#[derive(Debug, Clone)]
+pub struct MoveRecord {
+    pub turn: i32,
+    pub player: u8,
+    pub played: Action,
+    pub played_score: i32,
+    pub best: ActionAnalysis,
+}
+
+impl MoveRecord {
+    /// Score lost by playing this move instead of the best one
+    pub fn get_score_loss(&self) -> i32 {
+        self.best.score - self.played_score
+    }
+}
+
+/// Build a record of a played move from the analysis of the position
+///
+/// # Parameters
+///
+/// * analyses: Output of `analyze_legal_actions` for the position
+/// * turn: Turn count of the move
+/// * player: Player that played
+/// * played: Action played
+///
+/// # Returns
+///
+/// None if the played action is not in the analysis
+pub fn create_move_record(
+    analyses: &[ActionAnalysis],
+    turn: i32,
+    player: u8,
+    played: Action,
+) -> Option<MoveRecord> {
+    let played_score = analyses.iter().find(|a| a.action == played)?.score;
+    let best = get_best_analysis(analyses)?.clone();
+    Some(MoveRecord {
+        turn,
+        player,
+        played,
+        played_score,
+        best,
+    })
+}
+
+/// Analyze the position for the player (thin wrapper over the search)
+pub fn analyze_position(
+    state: &GameState,
+    player: u8,
+    depth: u8,
+    weights: &EvalWeights,
+) -> Vec<ActionAnalysis> {
+    if state.get_rules().turns.simultaneous {
+        analyze_simultaneous_actions(state, player, depth, weights)
+    } else {
+        analyze_legal_actions(state, player, depth, weights)
+    }
+}
+
+/// Label of a move given the score it lost
+pub fn classify_score_loss(loss: i32) -> &'static str {
+    if loss >= BLUNDER_THRESHOLD {
+        "BLUNDER"
+    } else if loss >= MISTAKE_THRESHOLD {
+        "MISTAKE"
+    } else if loss >= INACCURACY_THRESHOLD {
+        "INACCURACY"
+    } else {
+        "OK"
+    }
+}
+
+/// Render the evaluation bar, '1' is player 1 share and '2' player 2 share
+///
+/// # Parameters
+///
+/// * score_p1: Score seen from player 1
+///
+/// # Returns
+///
+/// Something like `P1 [1111111111222222222] P2 (+12)`
+pub fn render_evaluation_bar(score_p1: i32) -> String {
+    let clamped = score_p1.clamp(-WIN_SCORE, WIN_SCORE);
+    let p1_share = ((clamped + WIN_SCORE) as usize * EVALUATION_BAR_WIDTH) / (2 * WIN_SCORE as usize);
+    let mut bar = "1".repeat(p1_share);
+    bar.push_str(&"2".repeat(EVALUATION_BAR_WIDTH - p1_share));
+    format!("P1 [{}] P2 ({:+})", bar, score_p1)
+}
+
+/// Format a principal variation as `P1:Up P2:Left ...`
+pub fn format_principal_variation(line: &[(u8, Action)]) -> String {
+    line.iter()
+        .map(|(player, action)| format!("P{}:{:?}", player + 1, action))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Print every analyzed action with its score and line, best marked with '*'
+pub fn print_action_analysis(analyses: &[ActionAnalysis]) {
+    let best_action = get_best_analysis(analyses).map(|a| a.action);
+    for analysis in analyses {
+        let marker = if Some(analysis.action) == best_action { '*' } else { ' ' };
+        println!(
+            "{} {:<8} {:>6} | {}",
+            marker,
+            format!("{:?}", analysis.action),
+            analysis.score,
+            format_principal_variation(&analysis.principal_variation)
+        );
+    }
+}
+
+/// Print the per-move blunder report after the game
+pub fn print_blunder_report(records: &[MoveRecord]) {
+    println!("==== BLUNDER REPORT ====");
+    for record in records {
+        let loss = record.get_score_loss();
+        println!(
+            "TURN {:>3} P{} played {:<8} ({:>6}) best {:<8} ({:>6}) loss {:>5} {}",
+            record.turn,
+            record.player + 1,
+            format!("{:?}", record.played),
+            record.played_score,
+            format!("{:?}", record.best.action),
+            record.best.score,
+            loss,
+            classify_score_loss(loss)
+        );
+    }
+    for player in 0..2 {
+        let blunders = records
+            .iter()
+            .filter(|r| r.player == player && r.get_score_loss() >= BLUNDER_THRESHOLD)
+            .count();
+        println!("P{} BLUNDERS: {}", player + 1, blunders);
+    }
+}
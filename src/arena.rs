@@ -0,0 +1,62 @@
+//! Arena Module
+//!
+//! Shrinking arena (sudden death). Rings are numbered from the border:
+//! ring 0 is the outer ring, ring 1 the one inside, and so on.
+//! Ring `k` collapses after turn `start_turn + k * interval`.
+//!
+use crate::state_repr::{GameElements, GameState};
+
+/// Ring of a coordinate, 0 is the outer ring
+pub fn get_ring_of_coor(coor: (usize, usize), height_width: usize) -> usize {
+    let (x, y) = coor;
+    x.min(y).min(height_width - 1 - x).min(height_width - 1 - y)
+}
+
+/// Number of rings of a board
+pub fn get_ring_count(height_width: usize) -> usize {
+    height_width.div_ceil(2)
+}
+
+/// Turn after which a ring collapses, None if the arena doesn't shrink
+pub fn get_collapse_turn(state: &GameState, ring: usize) -> Option<u32> {
+    let shrink = &state.get_rules().shrink;
+    if !shrink.enabled || ring >= get_ring_count(state.get_height_width()) {
+        return None;
+    }
+    Some(shrink.start_turn + ring as u32 * shrink.interval.max(1))
+}
+
+/// Next collapse as (turn, ring), None if nothing collapses anymore
+pub fn get_next_collapse(state: &GameState) -> Option<(u32, usize)> {
+    (0..get_ring_count(state.get_height_width())).find_map(|ring| {
+        get_collapse_turn(state, ring)
+            .filter(|turn| *turn > state.get_turn_count())
+            .map(|turn| (turn, ring))
+    })
+}
+
+/// Collapse the ring due at the current turn, if any.
+/// Its tiles become `NonPassable` (the void stays void) and the players standing there are eliminated.
+pub fn shrink_arena(state: &mut GameState) {
+    let size = state.get_height_width();
+    let ring = (0..get_ring_count(size))
+        .find(|ring| get_collapse_turn(state, *ring) == Some(state.get_turn_count()));
+    let Some(ring) = ring else {
+        return;
+    };
+
+    for x in 0..size {
+        for y in 0..size {
+            let void = *state.get_elem_on_coor(x, y) == GameElements::Void;
+            if get_ring_of_coor((x, y), size) == ring && !void {
+                state.change_elem_on_coor(x, y, GameElements::NonPassable);
+            }
+        }
+    }
+    for player in 0..state.get_player_count() as u8 {
+        let (x, y) = state.get_player_position_with_idx(player);
+        if get_ring_of_coor((x as usize, y as usize), size) == ring {
+            state.eliminate_player(player);
+        }
+    }
+}
@@ -0,0 +1,469 @@
+//! Environment Module
+//!
+//! Gym-style wrapper around `GameState` for training agents.
+//!
+//! * `reset(seed)` creates a new reproducible map
+//! * `step(action)` plays the action for the player to move and returns
+//!   `(observation, reward, done, info)`
+//!
+//! Actions are indexes in the action space of the rules (see `get_action_space`).
+//! The reward is given to the player that acted: +1 win, -1 lose, 0 draw or game not ended.
+//! In fog mode the observation only holds what the player to move knows (`encode_view`).
+//! In simultaneous mode the actions of a round are kept until every player chose,
+//! then the round is resolved: each player observes the state at the start of the round.
+//!
+use strum::{EnumCount, IntoEnumIterator};
+
+use crate::action::{get_legal_action, get_players_to_act, Action, Direction, SkillTarget};
+use crate::fog::{get_fog_memories, remember_all, FogMemory, PlayerView};
+use crate::result::result_function::{result_after_action, result_after_round};
+use crate::rules::RuleSet;
+use crate::skill::SpecialSkill;
+use crate::state_repr::{GameElements, GameState, MapRandom, MAX_PLAYERS, PLAYER_ELEMENTS};
+use crate::terminal::{get_result_for_player, is_terminal_state};
+
+/// Actions without a skill slot: moves, then attacks, in every direction of any grid
+const BASE_ACTIONS: [Action; 16] = [
+    Action::Up,
+    Action::Down,
+    Action::Right,
+    Action::Left,
+    Action::UpRight,
+    Action::UpLeft,
+    Action::DownRight,
+    Action::DownLeft,
+    Action::Attack(Direction::Up),
+    Action::Attack(Direction::Down),
+    Action::Attack(Direction::Right),
+    Action::Attack(Direction::Left),
+    Action::Attack(Direction::UpRight),
+    Action::Attack(Direction::UpLeft),
+    Action::Attack(Direction::DownRight),
+    Action::Attack(Direction::DownLeft),
+];
+
+/// Number of scalar features after the planes:
+/// ATK of each player, HP of each player, player to move, turn count,
+/// count of each held special skill of each player
+/// (the `None` count is the number of free slots).
+/// Per-player features are padded with zeros up to `MAX_PLAYERS`.
+pub const FEATURE_COUNT: usize = 2 + MAX_PLAYERS * (2 + SpecialSkill::COUNT);
+
+/// Number of skill actions of one inventory slot: without aim,
+/// aimed at each direction, aimed at each tile
+fn get_slot_action_count(height_width: usize) -> usize {
+    1 + Direction::COUNT + height_width * height_width
+}
+
+/// Size of the action space of the rules
+pub fn get_action_space_size(rules: &RuleSet) -> usize {
+    BASE_ACTIONS.len() + rules.skills.inventory_size * get_slot_action_count(rules.board.size)
+}
+
+/// Fixed action space of the rules, an action is represented by its index here:
+/// moves, attacks, then for each inventory slot the special skill without aim,
+/// aimed at each direction, aimed at each tile
+pub fn get_action_space(rules: &RuleSet) -> Vec<Action> {
+    (0..get_action_space_size(rules))
+        .filter_map(|index| index_to_action(index, rules))
+        .collect()
+}
+
+/// Index of an action in the action space of the rules
+pub fn action_to_index(action: &Action, rules: &RuleSet) -> usize {
+    let height_width = rules.board.size;
+    let (slot, target) = match action {
+        Action::SpSkill(slot, target) => (*slot, target),
+        _ => {
+            return BASE_ACTIONS
+                .iter()
+                .position(|a| a == action)
+                .expect("Every other action is a move or an attack")
+        }
+    };
+    let target_idx = match target {
+        SkillTarget::None => 0,
+        SkillTarget::Direction(direction) => {
+            1 + Direction::iter()
+                .position(|d| d == *direction)
+                .expect("Every direction is iterated")
+        }
+        SkillTarget::Coordinate(x, y) => 1 + Direction::COUNT + x * height_width + y,
+    };
+    BASE_ACTIONS.len() + slot * get_slot_action_count(height_width) + target_idx
+}
+
+/// Action of an index in the action space of the rules, None if out of range
+pub fn index_to_action(index: usize, rules: &RuleSet) -> Option<Action> {
+    if index < BASE_ACTIONS.len() {
+        return Some(BASE_ACTIONS[index]);
+    }
+    if index >= get_action_space_size(rules) {
+        return None;
+    }
+    let height_width = rules.board.size;
+    let index = index - BASE_ACTIONS.len();
+    let slot = index / get_slot_action_count(height_width);
+    let target_idx = index % get_slot_action_count(height_width);
+    let target = if target_idx == 0 {
+        SkillTarget::None
+    } else if target_idx <= Direction::COUNT {
+        SkillTarget::Direction(Direction::iter().nth(target_idx - 1)?)
+    } else {
+        let tile = target_idx - 1 - Direction::COUNT;
+        SkillTarget::Coordinate(tile / height_width, tile % height_width)
+    };
+    Some(Action::SpSkill(slot, target))
+}
+
+/// Legal action mask of the player, aligned with the action space of the rules
+pub fn get_legal_action_mask(state: &GameState, player: u8) -> Vec<bool> {
+    let rules = state.get_rules();
+    let mut mask = vec![false; get_action_space_size(rules)];
+    for action in get_legal_action(state, player) {
+        mask[action_to_index(&action, rules)] = true;
+    }
+    mask
+}
+
+/// Tensor-like encoding of a state
+///
+/// `planes` is a flattened `[GameElements::COUNT, height_width, height_width]`
+/// tensor (plane, x, y) with one plane per `GameElements` kind.
+/// Player planes are taken from the positions of the players still in the game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub height_width: usize,
+    pub planes: Vec<f32>,
+    pub features: Vec<f32>,
+}
+
+impl Observation {
+    /// Shape of `planes`
+    pub fn get_planes_shape(&self) -> [usize; 3] {
+        [GameElements::COUNT, self.height_width, self.height_width]
+    }
+
+    /// Planes and features concatenated, handy for linear models
+    pub fn to_flat_vec(&self) -> Vec<f32> {
+        let mut flat = self.planes.clone();
+        flat.extend_from_slice(&self.features);
+        flat
+    }
+}
+
+/// Encode the state
+///
+/// # Parameters
+///
+/// * state: the state of the game
+/// * player_turn: Player to move
+///
+/// # Returns
+///
+/// Observation of the state
+pub fn encode_observation(state: &GameState, player_turn: u8) -> Observation {
+    let players = 0..state.get_player_count() as u8;
+    let tiles: Vec<Vec<Option<GameElements>>> = state
+        .get_map_representation()
+        .iter()
+        .map(|row| row.iter().map(|tile| Some(*tile)).collect())
+        .collect();
+    let positions: Vec<Option<(usize, usize)>> = players
+        .clone()
+        .map(|player| (!state.is_player_out(player)).then(|| state.get_player_coor(player)))
+        .collect();
+    let atk: Vec<i16> = players
+        .clone()
+        .map(|player| state.get_player_atk(player))
+        .collect();
+    let hp: Vec<i16> = players
+        .clone()
+        .map(|player| state.get_player_hp(player))
+        .collect();
+    let inventory_size = state.get_rules().skills.inventory_size;
+    let skill_counts = players
+        .map(|player| get_skill_counts(state.get_player_inventory(player), inventory_size))
+        .collect();
+    encode_known(
+        &tiles,
+        &positions,
+        &atk,
+        &hp,
+        skill_counts,
+        player_turn,
+        state.get_turn_count(),
+    )
+}
+
+/// Encode what a player knows in fog mode, same shape as `encode_observation`.
+/// Tiles never seen are zero on every plane, players out of sight are on no plane.
+/// The enemy skills are hidden: only their free slots are counted.
+///
+/// # Parameters
+///
+/// * view: the view of the player to move
+///
+/// # Returns
+///
+/// Observation of the view
+pub fn encode_view(view: &PlayerView) -> Observation {
+    let inventory_size = view.rules.skills.inventory_size;
+    let positions: Vec<Option<(usize, usize)>> = view
+        .positions
+        .iter()
+        .zip(view.out.iter())
+        .map(|(position, out)| position.filter(|_| !out))
+        .collect();
+    let skill_counts = (0..view.inventory_sizes.len())
+        .map(|idx| {
+            if idx == view.player as usize {
+                get_skill_counts(&view.inventory, inventory_size)
+            } else {
+                SpecialSkill::iter()
+                    .map(|sp| match sp {
+                        SpecialSkill::None => {
+                            inventory_size.saturating_sub(view.inventory_sizes[idx])
+                        }
+                        _ => 0,
+                    })
+                    .collect()
+            }
+        })
+        .collect();
+    encode_known(
+        &view.tiles,
+        &positions,
+        &view.atk,
+        &view.hp,
+        skill_counts,
+        view.player,
+        view.turn_count,
+    )
+}
+
+/// Count of each special skill of an inventory, the `None` count is the number of free slots
+fn get_skill_counts(inventory: &[SpecialSkill], inventory_size: usize) -> Vec<usize> {
+    SpecialSkill::iter()
+        .map(|sp| {
+            if sp == SpecialSkill::None {
+                inventory_size.saturating_sub(inventory.len())
+            } else {
+                inventory.iter().filter(|held| **held == sp).count()
+            }
+        })
+        .collect()
+}
+
+/// Planes and features of `encode_observation` and `encode_view`, None for what is unknown
+fn encode_known(
+    tiles: &[Vec<Option<GameElements>>],
+    positions: &[Option<(usize, usize)>],
+    atk: &[i16],
+    hp: &[i16],
+    skill_counts: Vec<Vec<usize>>,
+    player_turn: u8,
+    turn_count: u32,
+) -> Observation {
+    let size = tiles.len();
+    let plane_size = size * size;
+    let mut planes = vec![0.0; GameElements::COUNT * plane_size];
+
+    for (plane_idx, elem) in GameElements::iter().enumerate() {
+        let plane = &mut planes[plane_idx * plane_size..(plane_idx + 1) * plane_size];
+        match PLAYER_ELEMENTS.iter().position(|player_elem| *player_elem == elem) {
+            Some(player) => {
+                if let Some(Some((x, y))) = positions.get(player) {
+                    plane[x * size + y] = 1.0;
+                }
+            }
+            None => {
+                for (x, row) in tiles.iter().enumerate() {
+                    for (y, tile) in row.iter().enumerate() {
+                        if *tile == Some(elem) {
+                            plane[x * size + y] = 1.0;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let padding = MAX_PLAYERS - positions.len();
+    let mut features = Vec::with_capacity(FEATURE_COUNT);
+    features.extend(atk.iter().map(|atk| f32::from(*atk)));
+    features.extend(vec![0.0; padding]);
+    features.extend(hp.iter().map(|hp| f32::from(*hp)));
+    features.extend(vec![0.0; padding]);
+    features.push(f32::from(player_turn));
+    features.push(turn_count as f32);
+    for counts in skill_counts {
+        features.extend(counts.into_iter().map(|count| count as f32));
+    }
+    features.extend(vec![0.0; padding * SpecialSkill::COUNT]);
+
+    Observation {
+        height_width: size,
+        planes,
+        features,
+    }
+}
+
+/// Extra information of a step
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepInfo {
+    /// Player that played the action
+    pub player: u8,
+    /// Player to move now
+    pub next_player: u8,
+    /// Legal action mask of `next_player`
+    pub legal_action_mask: Vec<bool>,
+    /// Number of plies played (skipped turns included)
+    pub turn_count: u32,
+    /// The action was not legal, nothing happened
+    pub invalid_action: bool,
+}
+
+/// Gym-style game environment
+#[derive(Debug, Clone)]
+pub struct GameEnv {
+    rules: RuleSet,
+    map_randomness: MapRandom,
+    state: GameState,
+    /// Memory of every player in fog mode
+    memories: Vec<FogMemory>,
+    /// Actions chosen in the round so far (simultaneous mode)
+    round_actions: Vec<(u8, Action)>,
+    player_turn: u8,
+    turn_count: u32,
+}
+
+impl GameEnv {
+    ///
+    /// Instantiate the environment, the map is generated with seed 0
+    ///
+    /// # Parameters
+    /// rules: Rules of every game of the environment
+    ///
+    pub fn new(rules: RuleSet, map_randomness: MapRandom) -> GameEnv {
+        let state = GameState::new_with_rules(&rules, map_randomness, 0);
+        GameEnv {
+            memories: get_fog_memories(&state),
+            round_actions: Vec::new(),
+            state,
+            rules,
+            map_randomness,
+            player_turn: 0,
+            turn_count: 0,
+        }
+    }
+
+    /// Start a new game on the map of the seed
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.state = GameState::new_with_rules(&self.rules, self.map_randomness, seed);
+        self.memories = get_fog_memories(&self.state);
+        self.round_actions.clear();
+        self.player_turn = 0;
+        self.turn_count = 0;
+        self.skip_stuck_players();
+        self.get_observation()
+    }
+
+    ///
+    /// Play an action for the player to move.
+    /// Players without legal action are skipped afterwards, like on the CLI.
+    ///
+    /// # Parameters
+    /// action_index: index in the action space of the rules
+    ///
+    /// # Returns
+    /// (observation, reward of the player that acted, done, info)
+    ///
+    pub fn step(&mut self, action_index: usize) -> (Observation, f32, bool, StepInfo) {
+        let player = self.player_turn;
+        let legal = index_to_action(action_index, &self.rules)
+            .filter(|action| get_legal_action(&self.state, player).contains(action));
+
+        let invalid_action = match legal {
+            Some(action) if !self.is_done() && self.rules.turns.simultaneous => {
+                self.play_round_action(player, action);
+                self.turn_count += 1;
+                false
+            }
+            Some(action) if !self.is_done() => {
+                self.state = result_after_action(&self.state, &action, player);
+                remember_all(&mut self.memories, &self.state);
+                self.player_turn = (player + 1) % self.state.get_player_count() as u8;
+                self.turn_count += 1;
+                self.skip_stuck_players();
+                false
+            }
+            _ => true,
+        };
+
+        let done = self.is_done();
+        let reward = if done && !invalid_action {
+            f32::from(get_result_for_player(&self.state, player))
+        } else {
+            0.0
+        };
+        let info = StepInfo {
+            player,
+            next_player: self.player_turn,
+            legal_action_mask: self.get_legal_action_mask(),
+            turn_count: self.turn_count,
+            invalid_action,
+        };
+        (self.get_observation(), reward, done, info)
+    }
+
+    /// Legal action mask of the player to move
+    pub fn get_legal_action_mask(&self) -> Vec<bool> {
+        get_legal_action_mask(&self.state, self.player_turn)
+    }
+
+    /// Observation of the current state, the view of the player to move in fog mode
+    pub fn get_observation(&self) -> Observation {
+        match self.memories.get(self.player_turn as usize) {
+            Some(memory) => encode_view(&memory.clone().observe(&self.state)),
+            None => encode_observation(&self.state, self.player_turn),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        is_terminal_state(&self.state)
+    }
+
+    pub fn get_state(&self) -> &GameState {
+        &self.state
+    }
+
+    pub fn get_player_turn(&self) -> u8 {
+        self.player_turn
+    }
+
+    /// Keep the action until every player of the round chose, then resolve the round
+    fn play_round_action(&mut self, player: u8, action: Action) {
+        self.round_actions.push((player, action));
+        let next = get_players_to_act(&self.state)
+            .into_iter()
+            .find(|other| *other > player);
+        if let Some(next) = next {
+            self.player_turn = next;
+            return;
+        }
+        self.state = result_after_round(&self.state, &self.round_actions);
+        self.round_actions.clear();
+        remember_all(&mut self.memories, &self.state);
+        self.player_turn = 0;
+        self.skip_stuck_players();
+    }
+
+    /// Pass the turn while the player to move has no legal action
+    fn skip_stuck_players(&mut self) {
+        while !self.is_done() && get_legal_action(&self.state, self.player_turn).is_empty() {
+            self.player_turn = (self.player_turn + 1) % self.state.get_player_count() as u8;
+            self.turn_count += 1;
+        }
+    }
+}
@@ -0,0 +1,331 @@
+//! Fog Module
+//!
+//! Hidden information mode: a player only sees the tiles within
+//! `vision_radius` of its team, plus the tiles it saw before.
+//!
+//! * `FogMemory` keeps the tiles a player saw, it is updated with `observe`
+//! * `PlayerView` is what the player knows, the only thing bots get in fog mode
+//! * `PlayerView::determinize` draws a full state consistent with the view,
+//!   so the usual search can run on it
+//!
+//! Enemies out of sight and their special skills are hidden, ATK and HP are public.
+//!
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use strum::IntoEnumIterator;
+
+use crate::action::{get_available_action, Action};
+use crate::rules::RuleSet;
+use crate::skill::SpecialSkill;
+use crate::topology::Topology;
+use crate::state_repr::{
+    is_passable_elem, transform_enum_elem_to_char, GameElements, GameState, MapRandom,
+    TeleporterPair, PLAYER_ELEMENTS,
+};
+
+/// Character of a tile never seen
+pub const UNKNOWN_TILE_CHAR: char = '?';
+
+/// Whether the player sees the tile: within `vision_radius` (at least 1, steps on
+/// an empty board) of the player or of a team-mate still in the game. Always true without fog.
+pub fn is_visible(state: &GameState, player: u8, coor: (usize, usize)) -> bool {
+    let fog = &state.get_rules().fog;
+    if !fog.enabled {
+        return true;
+    }
+    let radius = fog.vision_radius.max(1);
+    let team = state.get_team(player);
+    let topology = state.get_topology();
+    (0..state.get_player_count() as u8)
+        .filter(|other| {
+            *other == player || (state.get_team(*other) == team && !state.is_player_out(*other))
+        })
+        .any(|other| topology.get_distance(state.get_player_coor(other), coor) <= radius)
+}
+
+/// Grid of the tiles the player sees right now
+pub fn get_visible_tiles(state: &GameState, player: u8) -> Vec<Vec<bool>> {
+    let size = state.get_height_width();
+    (0..size)
+        .map(|x| (0..size).map(|y| is_visible(state, player, (x, y))).collect())
+        .collect()
+}
+
+/// Memory of every player, empty without fog
+pub fn get_fog_memories(state: &GameState) -> Vec<FogMemory> {
+    if !state.get_rules().fog.enabled {
+        return Vec::new();
+    }
+    let mut memories: Vec<FogMemory> = (0..state.get_player_count() as u8)
+        .map(|player| FogMemory::new(player, state.get_height_width()))
+        .collect();
+    remember_all(&mut memories, state);
+    memories
+}
+
+/// Every player remembers the tiles it sees now
+pub fn remember_all(memories: &mut [FogMemory], state: &GameState) {
+    for memory in memories.iter_mut() {
+        memory.remember(state);
+    }
+}
+
+/// What a player knows about the game
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerView {
+    pub player: u8,
+    pub height_width: usize,
+    pub turn_count: u32,
+    /// Tile of each coordinate as last seen, None if never seen
+    pub tiles: Vec<Vec<Option<GameElements>>>,
+    /// Cells outside the board, the shape of the map is known
+    pub void: Vec<Vec<bool>>,
+    /// Generator of the map, None for a hand-authored map
+    pub map_randomness: Option<MapRandom>,
+    /// Tiles seen right now
+    pub visible: Vec<Vec<bool>>,
+    /// Position of each player, None if out of sight
+    pub positions: Vec<Option<(usize, usize)>>,
+    /// Team of each player
+    pub teams: Vec<u8>,
+    pub atk: Vec<i16>,
+    pub hp: Vec<i16>,
+    /// Players out of the game
+    pub out: Vec<bool>,
+    /// Players caught by the arena
+    pub eliminated: Vec<bool>,
+    /// Own special skills, oldest first
+    pub inventory: Vec<SpecialSkill>,
+    /// Number of special skills each player holds
+    pub inventory_sizes: Vec<usize>,
+    /// Own wait (traps)
+    pub wait: u8,
+    /// Legal actions of the player, from the view (see `get_known_actions`)
+    pub legal_actions: Vec<Action>,
+    pub rules: RuleSet,
+}
+
+/// Tiles a player saw during the game
+#[derive(Debug, Clone, PartialEq)]
+pub struct FogMemory {
+    player: u8,
+    tiles: Vec<Vec<Option<GameElements>>>,
+}
+
+impl FogMemory {
+    pub fn new(player: u8, height_width: usize) -> FogMemory {
+        FogMemory {
+            player,
+            tiles: vec![vec![None; height_width]; height_width],
+        }
+    }
+
+    /// Remember the tiles the player sees now
+    pub fn remember(&mut self, state: &GameState) -> Vec<Vec<bool>> {
+        let visible = get_visible_tiles(state, self.player);
+        for (x, row) in visible.iter().enumerate() {
+            for (y, seen) in row.iter().enumerate() {
+                if *seen {
+                    self.tiles[x][y] = Some(*state.get_elem_on_coor(x, y));
+                }
+            }
+        }
+        visible
+    }
+
+    ///
+    /// Remember the tiles the player sees now and build its view
+    ///
+    /// # Parameters
+    /// state: the true state of the game
+    ///
+    pub fn observe(&mut self, state: &GameState) -> PlayerView {
+        let player = self.player;
+        let visible = self.remember(state);
+
+        let player_count = state.get_player_count() as u8;
+        let positions = (0..player_count)
+            .map(|other| {
+                let (x, y) = state.get_player_coor(other);
+                let seen = other == player || (!state.is_player_out(other) && visible[x][y]);
+                seen.then_some((x, y))
+            })
+            .collect();
+        let mut view = PlayerView {
+            player,
+            height_width: state.get_height_width(),
+            turn_count: state.get_turn_count(),
+            tiles: self.tiles.clone(),
+            void: state
+                .get_map_representation()
+                .iter()
+                .map(|row| row.iter().map(|tile| *tile == GameElements::Void).collect())
+                .collect(),
+            map_randomness: state.get_map_randomness(),
+            visible,
+            positions,
+            teams: (0..player_count).map(|other| state.get_team(other)).collect(),
+            atk: (0..player_count).map(|other| state.get_player_atk(other)).collect(),
+            hp: (0..player_count).map(|other| state.get_player_hp(other)).collect(),
+            out: (0..player_count).map(|other| state.is_player_out(other)).collect(),
+            eliminated: (0..player_count)
+                .map(|other| state.is_player_eliminated(other))
+                .collect(),
+            inventory: state.get_player_inventory(player).to_vec(),
+            inventory_sizes: (0..player_count)
+                .map(|other| state.get_player_inventory(other).len())
+                .collect(),
+            wait: state.get_player_wait(player),
+            legal_actions: Vec::new(),
+            rules: state.get_rules().clone(),
+        };
+        view.legal_actions = view.get_known_actions();
+        view
+    }
+}
+
+impl PlayerView {
+    ///
+    /// Actions of the player from what it knows: the actions on a state drawn
+    /// from the view, they only depend on the tiles and the enemies it sees.
+    /// Traps are left to the game, a waiting player is only asked when it can play.
+    ///
+    pub fn get_known_actions(&self) -> Vec<Action> {
+        let mut rng = StdRng::seed_from_u64(self.turn_count as u64);
+        get_available_action(&self.determinize(&mut rng), self.player)
+    }
+
+    ///
+    /// Draw a full state consistent with the view.
+    /// The map keeps its shape, the tiles never seen are drawn by the generator
+    /// of the map (with the generation weights for a hand-authored map) and the
+    /// teleporters are paired at random. Enemies out of sight stand on a random
+    /// passable tile out of sight and hold random skills.
+    ///
+    /// # Parameters
+    /// rng: Random generator of the draw
+    ///
+    pub fn determinize(&self, rng: &mut impl Rng) -> GameState {
+        let map_randomness = self.map_randomness.unwrap_or(MapRandom::FullyRandom);
+        let mut state = GameState::new_with_rules(&self.rules, map_randomness, rng.gen());
+        // players knocked out now are out from this turn
+        state.set_turn_count(self.turn_count);
+        state.set_teleporter_pairs(&[]);
+        for (x, row) in self.tiles.iter().enumerate() {
+            for (y, tile) in row.iter().enumerate() {
+                if self.void[x][y] {
+                    state.change_elem_on_coor(x, y, GameElements::Void);
+                } else if let Some(tile) = tile {
+                    state.change_elem_on_coor(x, y, *tile);
+                }
+            }
+        }
+        let mut teleporters: Vec<(usize, usize)> = (0..self.height_width)
+            .flat_map(|x| (0..self.height_width).map(move |y| (x, y)))
+            .filter(|(x, y)| *state.get_elem_on_coor(*x, *y) == GameElements::TeleporterTile)
+            .collect();
+        teleporters.shuffle(rng);
+        let pairs: Vec<TeleporterPair> = teleporters
+            .chunks_exact(2)
+            .map(|pair| [pair[0], pair[1]])
+            .collect();
+        state.set_teleporter_pairs(&pairs);
+
+        // hidden tiles an enemy could stand on
+        let mut hidden: Vec<(usize, usize)> = (0..self.height_width)
+            .flat_map(|x| (0..self.height_width).map(move |y| (x, y)))
+            .filter(|(x, y)| !self.visible[*x][*y] && !self.void[*x][*y])
+            .collect();
+        hidden.shuffle(rng);
+        let free_hidden: Vec<(usize, usize)> = hidden
+            .iter()
+            .filter(|(x, y)| is_passable_elem(state.get_elem_on_coor(*x, *y)))
+            .chain(hidden.iter())
+            .copied()
+            .collect();
+        let mut free_hidden = free_hidden.into_iter();
+        let own_position = self.positions[self.player as usize].unwrap_or((0, 0));
+
+        let skills: Vec<SpecialSkill> = SpecialSkill::iter()
+            .filter(|skill| *skill != SpecialSkill::None)
+            .collect();
+        for player in 0..self.positions.len() as u8 {
+            let idx = player as usize;
+            let (x, y) = self.positions[idx]
+                .or_else(|| free_hidden.next())
+                .unwrap_or(own_position);
+            state.change_player_pos(player, x, y);
+            state.change_player_atk(player, self.atk[idx] - state.get_player_atk(player));
+            state.change_player_hp(player, self.hp[idx] - state.get_player_hp(player));
+            if self.eliminated[idx] {
+                state.eliminate_player(player);
+            }
+            if player == self.player {
+                for skill in self.inventory.iter() {
+                    state.add_player_sp(player, skill.clone());
+                }
+                state.set_player_wait(player, self.wait);
+            } else {
+                for _ in 0..self.inventory_sizes[idx] {
+                    if let Some(skill) = skills.choose(rng) {
+                        state.add_player_sp(player, skill.clone());
+                    }
+                }
+            }
+        }
+        state
+    }
+
+    /// Print the view: `?` for tiles never seen, tiles out of sight as last seen
+    pub fn print_view(&self) {
+        let line = |name: &str, values: Vec<String>| {
+            let values: Vec<String> = values
+                .iter()
+                .enumerate()
+                .map(|(idx, value)| format!("{} P{} = {}", name, idx + 1, value))
+                .collect();
+            println!("{}", values.join(" \t "));
+        };
+        line("ATK", self.atk.iter().map(|atk| atk.to_string()).collect());
+        if self.rules.combat.enabled {
+            line("HP", self.hp.iter().map(|hp| hp.to_string()).collect());
+        }
+        line(
+            "SP SKILL",
+            (0..self.inventory_sizes.len())
+                .map(|idx| {
+                    if idx == self.player as usize {
+                        format!("{:?}", self.inventory)
+                    } else {
+                        format!("{} hidden", self.inventory_sizes[idx])
+                    }
+                })
+                .collect(),
+        );
+        println!(
+            "FOG: vision {} around P{}",
+            self.rules.fog.vision_radius.max(1),
+            self.player + 1
+        );
+        let topology = Topology::new(&self.rules.board);
+        for y in 0..self.height_width {
+            let mut collect_str: String = topology.get_row_indent(y);
+            for x in 0..self.height_width {
+                let player = self
+                    .positions
+                    .iter()
+                    .position(|position| *position == Some((x, y)));
+                let tile_char = match (player, self.tiles[x][y]) {
+                    (Some(player), _) => transform_enum_elem_to_char(&PLAYER_ELEMENTS[player]),
+                    (None, Some(tile)) => transform_enum_elem_to_char(&tile),
+                    (None, None) => UNKNOWN_TILE_CHAR,
+                };
+                collect_str.push(tile_char);
+                collect_str.push(' ');
+            }
+            println!("{}", collect_str);
+        }
+        println!("---")
+    }
+}
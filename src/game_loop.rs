@@ -0,0 +1,92 @@
+//! Game Loop Module
+//!
+//! Headless game loop: one agent per player plays a full game without any printing.
+//! Used by everything that needs bot-vs-bot games (tuning, map analysis...).
+//! In fog mode the agents only get the view of their player.
+//!
+use crate::action::{
+    get_first_player_to_move, get_next_player_to_move, get_players_to_act, Action,
+};
+use crate::agent::base::Agent;
+use crate::fog::{get_fog_memories, remember_all, FogMemory};
+use crate::result::result_function::{result_after_action, result_after_round};
+use crate::state_repr::GameState;
+use crate::terminal::is_terminal_state;
+
+/// Play a game between agents.
+/// In simultaneous mode every agent chooses on the state at the start of the round.
+///
+/// # Parameters
+///
+/// * state: Starting state
+/// * agents: Agent of each player, in player order
+///
+/// # Returns
+///
+/// The final state and the number of plies played
+pub fn play_headless_game(state: &GameState, agents: &mut [&mut dyn Agent]) -> (GameState, u32) {
+    if state.get_rules().turns.simultaneous {
+        return play_headless_rounds(state, agents);
+    }
+    let mut state = state.clone();
+    let mut memories = get_fog_memories(&state);
+    let mut player = get_first_player_to_move(&state);
+    let mut plies = 0;
+
+    while !is_terminal_state(&state) {
+        let agent = &mut *agents[player as usize];
+        let Some(action) = select_agent_action(agent, &state, player, &mut memories) else {
+            break;
+        };
+        state = result_after_action(&state, &action, player);
+        remember_all(&mut memories, &state);
+        player = get_next_player_to_move(&state, player);
+        plies += 1;
+    }
+    (state, plies)
+}
+
+/// Simultaneous mode of `play_headless_game`
+fn play_headless_rounds(state: &GameState, agents: &mut [&mut dyn Agent]) -> (GameState, u32) {
+    let mut state = state.clone();
+    let mut memories = get_fog_memories(&state);
+    let mut plies = 0;
+
+    while !is_terminal_state(&state) {
+        let actions: Vec<_> = get_players_to_act(&state)
+            .into_iter()
+            .filter_map(|player| {
+                let agent = &mut *agents[player as usize];
+                let action = select_agent_action(agent, &state, player, &mut memories)?;
+                Some((player, action))
+            })
+            .collect();
+        if actions.is_empty() {
+            break;
+        }
+        state = result_after_round(&state, &actions);
+        remember_all(&mut memories, &state);
+        plies += actions.len() as u32;
+    }
+    (state, plies)
+}
+
+/// Ask the agent for an action, from the view of the player in fog mode
+///
+/// # Parameters
+///
+/// * agent: Agent of the player
+/// * state: the state of the game
+/// * player: Player to move
+/// * memories: Memory of every player, empty without fog
+pub fn select_agent_action(
+    agent: &mut dyn Agent,
+    state: &GameState,
+    player: u8,
+    memories: &mut [FogMemory],
+) -> Option<Action> {
+    match memories.get_mut(player as usize) {
+        Some(memory) => agent.select_action_from_view(&memory.observe(state)),
+        None => agent.select_action(state, player),
+    }
+}
@@ -1,55 +1,338 @@
-use crate::action::{Action, get_legal_action};
-use crate::result::result_function::result_after_action;
+use std::path::PathBuf;
+
+use crate::action::{Action, Direction, SkillTarget, get_legal_action, get_players_to_act};
+use crate::agent::base::Agent;
+use crate::agent::q_learning::{QLearningAgent, QTable};
+use crate::agent::search_agent::{MctsAgent, MinimaxAgent};
+use crate::analysis::{
+    analyze_position, create_move_record, print_action_analysis, print_blunder_report,
+    render_evaluation_bar, MoveRecord,
+};
+use crate::fog::{get_fog_memories, remember_all, FogMemory};
+use crate::game_loop::select_agent_action;
+use crate::map::GameMap;
+use crate::result::result_function::{result_after_action, result_after_round};
+use crate::rules::RuleSet;
+use crate::search::evaluation::EvalWeights;
+use crate::search::mcts::MctsConfig;
+use crate::search::minimax::{get_best_analysis, ActionAnalysis, DEFAULT_SEARCH_DEPTH};
 use crate::state_repr::{GameState, MapRandom};
-use crate::terminal::{get_point_for_player_on_terminal, is_terminal_state};
+use crate::terminal::{get_game_outcome, GameOutcome};
+
 
+/// Bot playing every player but player 1
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpponentKind {
+    Minimax,
+    Mcts,
+    /// Q-learning agent loaded from a table file
+    QLearning(PathBuf),
+}
+
+/// Options of a CLI game
+#[derive(Debug, Clone)]
+pub struct PlayOptions {
+    /// Show the engine analysis every turn and the blunder report at the end (or on quit)
+    pub analysis_mode: bool,
+    /// Search depth used by hints, analysis and the minimax opponent
+    pub search_depth: u8,
+    /// Rules of the game (board size included)
+    pub rules: RuleSet,
+    /// None: the other players are humans too
+    pub opponent: Option<OpponentKind>,
+    /// Evaluation used by hints, analysis and the minimax opponent
+    pub eval_weights: EvalWeights,
+    /// Hand-authored map, None for a random map
+    pub map: Option<GameMap>,
+    /// Generator of the random map
+    pub map_randomness: MapRandom,
+}
 
-/// GAME MAP SIZE
-const MAP_SIZE: usize = 5;
+impl Default for PlayOptions {
+    fn default() -> Self {
+        PlayOptions {
+            analysis_mode: false,
+            search_depth: DEFAULT_SEARCH_DEPTH,
+            rules: RuleSet::default(),
+            opponent: None,
+            eval_weights: EvalWeights::default(),
+            map: None,
+            map_randomness: MapRandom::FullyRandom,
+        }
+    }
+}
+
+/// Create the bot of the options
+fn create_opponent(kind: &OpponentKind, options: &PlayOptions) -> Result<Box<dyn Agent>, String> {
+    let seed = rand::random();
+    match kind {
+        OpponentKind::Minimax => Ok(Box::new(MinimaxAgent::new(
+            options.search_depth,
+            options.eval_weights,
+        ))),
+        OpponentKind::Mcts => Ok(Box::new(MctsAgent::new(MctsConfig::default(), seed))),
+        OpponentKind::QLearning(path) => {
+            let table = QTable::load(path, &options.rules)
+                .map_err(|err| format!("Can't load Q-table {}: {err}", path.display()))?;
+            Ok(Box::new(QLearningAgent::new(table, seed)))
+        }
+    }
+}
+
+/// Print the evaluation bar and the analysis of every legal action
+fn print_hint(analyses: &[ActionAnalysis], player_turn: u8) {
+    if let Some(best) = get_best_analysis(analyses) {
+        let score_p1 = if player_turn == 0 { best.score } else { -best.score };
+        println!("{}", render_evaluation_bar(score_p1));
+    }
+    print_action_analysis(analyses);
+}
 
 ///
 /// Translate move input by the user to its Action
 /// 
 /// # Parameters
 ///
-/// * user_inp String of user
+/// * user_inp String of user, like `u`, `ur`, `a u`, `s`, `s u`, `s 3,4` or `s2 u`
 /// 
 /// # Returns
 /// 
 /// None or Action
 ///
-fn translate_move(user_inp: &String) -> Option<Action> {
-    match user_inp.trim() {
-        "u" => Some(Action::Up),
-        "l" => Some(Action::Left),
-        "r" => Some(Action::Right),
-        "d" => Some(Action::Down),
-        "s" => Some(Action::SpSkill),
+pub fn translate_move(user_inp: &str) -> Option<Action> {
+    let mut words = user_inp.split_whitespace();
+    let first = words.next()?;
+    let action = match translate_direction(first) {
+        Some(direction) => Action::from_move_direction(direction),
+        None if first == "a" => match translate_skill_target(words.next())? {
+            SkillTarget::Direction(direction) => Action::Attack(direction),
+            _ => return None,
+        },
+        None => {
+            // `s` uses the first slot, `s2` the second one...
+            let slot = match first.strip_prefix('s')? {
+                "" => 0,
+                number => number.parse::<usize>().ok()?.checked_sub(1)?,
+            };
+            Action::SpSkill(slot, translate_skill_target(words.next())?)
+        }
+    };
+    // nothing may follow the action
+    match words.next() {
+        None => Some(action),
+        Some(_) => None,
+    }
+}
+
+/// Direction typed by the user: `u`, `l`, `r`, `d`, or `ur`, `ul`, `dr`, `dl` for the diagonals
+fn translate_direction(direction_inp: &str) -> Option<Direction> {
+    match direction_inp {
+        "u" => Some(Direction::Up),
+        "l" => Some(Direction::Left),
+        "r" => Some(Direction::Right),
+        "d" => Some(Direction::Down),
+        "ur" => Some(Direction::UpRight),
+        "ul" => Some(Direction::UpLeft),
+        "dr" => Some(Direction::DownRight),
+        "dl" => Some(Direction::DownLeft),
         _ => None,
     }
 }
 
+///
+/// Translate the aim of a special skill
+///
+/// # Parameters
+///
+/// * target_inp: None, a direction (`u`, `l`, `r`, `d`, `ur`...) or a coordinate `x,y`
+///
+fn translate_skill_target(target_inp: Option<&str>) -> Option<SkillTarget> {
+    let target = match target_inp {
+        None => SkillTarget::None,
+        Some(inp) => match translate_direction(inp) {
+            Some(direction) => SkillTarget::Direction(direction),
+            None => {
+                let (x, y) = inp.split_once(',')?;
+                SkillTarget::Coordinate(x.trim().parse().ok()?, y.trim().parse().ok()?)
+            }
+        },
+    };
+    Some(target)
+}
+
+/// Print the board for the viewer, only its view in fog mode
+fn print_board_for(state: &GameState, viewer: u8, memories: &mut [FogMemory]) {
+    match memories.get_mut(viewer as usize) {
+        Some(memory) => memory.observe(state).print_view(),
+        None => state.print_pretty_state(),
+    }
+}
+
+/// Print the engine analysis for the player, not available in fog mode
+fn print_hint_if_allowed(state: &GameState, player: u8, options: &PlayOptions) {
+    if state.get_rules().fog.enabled {
+        println!("No hint in fog mode!");
+        return;
+    }
+    print_hint(
+        &analyze_position(state, player, options.search_depth, &options.eval_weights),
+        player,
+    );
+}
+
+/// Print the end of the game: why it ended, the places and the result of player 1
+fn print_game_over(state: &GameState, outcome: &GameOutcome) {
+    state.print_pretty_state();
+    println!(
+        "GAME OVER after {} turns: {}",
+        outcome.turn_count,
+        outcome.reason.get_description()
+    );
+    if state.get_rules().board.teams {
+        for (team, place) in outcome.team_ranking.iter().enumerate() {
+            println!("TEAM {} PLACE {}", team + 1, place);
+        }
+    } else if state.get_player_count() > 2 {
+        for (player, place) in outcome.ranking.iter().enumerate() {
+            println!("PLAYER {} PLACE {}", player + 1, place);
+        }
+    }
+    match outcome.get_result_for_player(0) {
+        1 => println!("PLAYER 1 WIN!"),
+        -1 => println!("PLAYER 1 LOSE!"),
+        _ => println!("DRAWWW!!"),
+    }
+}
+
+///
+/// Read the action of a human player until it is legal
+///
+/// # Returns
+///
+/// The action, None if the player quitted
+///
+fn read_round_action(
+    state: &GameState,
+    player: u8,
+    options: &PlayOptions,
+    memories: &mut [FogMemory],
+) -> Option<Action> {
+    let legal_action = get_legal_action(state, player);
+    if !memories.is_empty() {
+        print_board_for(state, player, memories);
+    }
+    loop {
+        println!("PLAYER {} | Possible Action: {:?}", player + 1, legal_action);
+        println!("YOUR MOVE (hidden until everyone chose): ");
+        let mut action = String::new();
+        std::io::stdin()
+            .read_line(&mut action)
+            .expect("Failed to read message");
+        match translate_move(&action) {
+            Some(act) if legal_action.contains(&act) => return Some(act),
+            Some(act) => println!("INVALID MOVE FOR {:#?}, CHOOSE AGAIN!", act),
+            None if action.trim() == "q" || action.is_empty() => {
+                println!("You Quitted!");
+                return None;
+            }
+            None if action.trim() == "hint" => print_hint_if_allowed(state, player, options),
+            None => println!("Your inp {action} is wrong! put it again!"),
+        }
+    }
+}
+
+/// Simultaneous mode on CLI: every player chooses an action on the same state,
+/// nobody sees the others, then the round is resolved
+fn play_rounds_on_cli(
+    options: &PlayOptions,
+    mut state: GameState,
+    mut opponents: Vec<Option<Box<dyn Agent>>>,
+) {
+    let mut memories = get_fog_memories(&state);
+    let mut count_round = 1;
+    loop {
+        if let Some(outcome) = get_game_outcome(&state) {
+            print_game_over(&state, &outcome);
+            return;
+        }
+        println!("ROUND {}", count_round);
+        println!("====");
+        if memories.is_empty() {
+            state.print_pretty_state();
+        }
+
+        let mut actions = Vec::new();
+        for player in get_players_to_act(&state) {
+            let action = match opponents[player as usize].as_mut() {
+                Some(agent) => {
+                    println!("{} HAS CHOSEN", agent.get_name());
+                    select_agent_action(agent.as_mut(), &state, player, &mut memories)
+                }
+                None => match read_round_action(&state, player, options, &mut memories) {
+                    Some(action) => Some(action),
+                    None => return,
+                },
+            };
+            if let Some(action) = action {
+                actions.push((player, action));
+            }
+        }
+        if memories.is_empty() {
+            for (player, action) in actions.iter() {
+                println!("PLAYER {} PLAYS {:?}", player + 1, action);
+            }
+        }
+        state = result_after_round(&state, &actions);
+        remember_all(&mut memories, &state);
+        count_round += 1;
+    }
+}
+
 /// Start the game on CLI
-pub fn start_game_on_cli() {
+pub fn start_game_on_cli(options: &PlayOptions) {
+    let state = match options.map.as_ref() {
+        Some(map) => GameState::new_with_map(&options.rules, map, rand::random()),
+        None => Ok(GameState::new_with_rules(&options.rules, options.map_randomness, rand::random())),
+    };
+    let mut state = match state {
+        Ok(state) => state,
+        Err(message) => {
+            println!("{message}");
+            return;
+        }
+    };
     println!("You'll act!");
-
-    let mut state: GameState = GameState::new(MAP_SIZE, MapRandom::FullyRandom);
+    let player_count = state.get_player_count() as u8;
+    // one bot for each seat after player 1
+    let mut opponents: Vec<Option<Box<dyn Agent>>> = vec![None];
+    for _ in 1..player_count {
+        match options.opponent.as_ref().map(|kind| create_opponent(kind, options)) {
+            Some(Err(message)) => {
+                println!("{message}");
+                return;
+            }
+            opponent => opponents.push(opponent.and_then(Result::ok)),
+        }
+    }
+    if options.rules.turns.simultaneous {
+        play_rounds_on_cli(options, state, opponents);
+        return;
+    }
+    // in fog mode every player only sees its own view
+    let mut memories = get_fog_memories(&state);
     // Check possible move
     let mut action = String::new();
     let mut player_turn = 0;
     let mut count_turn = 1_i32;
+    let mut move_records: Vec<MoveRecord> = Vec::new();
 
     while action.trim() != "q" {
 
         // Check terminal first
-        if is_terminal_state(&state) {
-            let p_p1 = get_point_for_player_on_terminal(&state, true);
-            if p_p1 > 0 {
-                println!("PLAYER 1 WIN!");
-            } else if p_p1 < 0 {
-                println!("PLAYER 1 LOSE!");
-            } else {
-                println!("DRAWWW!!")
+        if let Some(outcome) = get_game_outcome(&state) {
+            print_game_over(&state, &outcome);
+            if options.analysis_mode {
+                print_blunder_report(&move_records);
             }
             break;
         }
@@ -57,14 +340,38 @@ pub fn start_game_on_cli() {
         action = "".to_string();
         println!("PLAYER {} | COUNT TURN {}", player_turn + 1, count_turn);
         println!("====");
-        println!("Movement: \nu: up \nl: left \nr: right \nd: down \ns: special skill");
+        println!("Movement: \nu: up \nl: left \nr: right \nd: down \nur, ul, dr, dl: diagonals (8-direction and hex grids) \ns: special skill (aim with `s u` or `s x,y`, other slots with `s2`, `s3`...) \na: attack (combat mode, `a u`) \nhint: engine analysis");
         println!("====");
-        state.print_pretty_state();
+        // bots never show their view, player 1 is the human when bots play
+        let viewer = if opponents[player_turn as usize].is_none() { player_turn } else { 0 };
+        print_board_for(&state, viewer, &mut memories);
         let legal_action = get_legal_action(&state, player_turn);
 
-        if legal_action.len() > 0 {
+        if let (Some(agent), false) = (opponents[player_turn as usize].as_mut(), legal_action.is_empty()) {
+            if let Some(act) = select_agent_action(agent.as_mut(), &state, player_turn, &mut memories) {
+                if memories.is_empty() {
+                    println!("{} PLAYS {:?}", agent.get_name(), act);
+                } else {
+                    println!("{} HAS PLAYED", agent.get_name());
+                }
+                state = result_after_action(&state, &act, player_turn);
+                remember_all(&mut memories, &state);
+                count_turn += 1;
+                player_turn = (player_turn + 1) % player_count;
+                continue;
+            }
+        }
+
+        if !legal_action.is_empty() {
             // Check legal action 
             println!("Possible Action: {:#?}", legal_action);
+            let analyses = if options.analysis_mode && memories.is_empty() {
+                let analyses = analyze_position(&state, player_turn, options.search_depth, &options.eval_weights);
+                print_hint(&analyses, player_turn);
+                analyses
+            } else {
+                Vec::new()
+            };
             println!("YOUR MOVE : ");
             std::io::stdin()
                 .read_line(&mut action)
@@ -72,7 +379,13 @@ pub fn start_game_on_cli() {
             match translate_move(&action) {
                 None => {
                     if action.trim() == "q" {
-                        println!("You Quitted!")
+                        println!("You Quitted!");
+                        // the moves played so far are still worth a look
+                        if options.analysis_mode {
+                            print_blunder_report(&move_records);
+                        }
+                    } else if action.trim() == "hint" {
+                        print_hint_if_allowed(&state, player_turn, options);
                     } else {
                         println!("Your inp {action} is wrong! put it again!")
                     }
@@ -80,11 +393,17 @@ pub fn start_game_on_cli() {
                 Some(act) => {
                     // check if act is valid, if not ask user to inp!
                     if legal_action.contains(&act) {
+                        if let Some(record) =
+                            create_move_record(&analyses, count_turn, player_turn, act)
+                        {
+                            move_records.push(record);
+                        }
                         let new_state = result_after_action(&state, &act, player_turn);
                         // check terminal
                         state = new_state;
+                        remember_all(&mut memories, &state);
                         count_turn += 1;
-                        player_turn = 1 - player_turn;
+                        player_turn = (player_turn + 1) % player_count;
                     }
                     else {
                         println!("INVALID MOVE FOR {:#?}, CHOOSE AGAIN!", act);
@@ -96,9 +415,8 @@ pub fn start_game_on_cli() {
         else {
             // No valid move, SKIP turn
             println!("YOU DONT HAVE ANY MOVEMENT! SKIPPED");
-            state = state;
             count_turn += 1;
-            player_turn = 1 - player_turn;
+            player_turn = (player_turn + 1) % player_count;
         }
     }
 }
@@ -0,0 +1,408 @@
+//! Command Module
+//!
+//! Parse the command line arguments into the command to run.
+//!
+//! * no subcommand: play on the CLI
+//! * `selfplay`: generate training data
+//! * `train-q`: train a Q-learning agent
+//! * `tune`: tune the evaluation weights
+//! * `edit`: edit a map
+//! * `analyze-map`: fairness of maps
+//! * `puzzle`: play a generated puzzle
+//!
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::agent::q_learning::QLearningConfig;
+use crate::interface::cli::{OpponentKind, PlayOptions};
+use crate::interface::editor::EditorOptions;
+use crate::map::{find_map, get_builtin_map, get_builtin_map_names, GameMap};
+use crate::map_analysis::FairnessConfig;
+use crate::puzzle::{Difficulty, GoalKind, PuzzleConfig};
+use crate::rules::{GridKind, RuleSet, MAX_BOARD_SIZE, MIN_BOARD_SIZE};
+use crate::search::evaluation::EvalWeights;
+use crate::selfplay::SelfPlayConfig;
+use crate::state_repr::{GameState, MapRandom, MAX_PLAYERS};
+use crate::tuning::TuningConfig;
+
+/// Command to run
+#[derive(Debug, Clone)]
+pub enum Command {
+    Play(PlayOptions),
+    SelfPlay(SelfPlayConfig),
+    /// Training settings and the output file of the table
+    TrainQ(QLearningConfig, PathBuf),
+    /// Tuning settings and the output file of the weights
+    Tune(TuningConfig, PathBuf),
+    Edit(EditorOptions),
+    /// Analysis settings and the maps to analyze
+    AnalyzeMaps(FairnessConfig, Vec<GameMap>),
+    Puzzle(PuzzleConfig),
+}
+
+///
+/// Parse the command line arguments (program name excluded)
+///
+/// # Parameters
+///
+/// * args: Arguments of the program
+///
+/// # Returns
+///
+/// The command or a message explaining the wrong argument
+///
+pub fn parse_command(args: &[String]) -> Result<Command, String> {
+    match args.first().map(String::as_str) {
+        Some("selfplay") => parse_selfplay_config(&args[1..]).map(Command::SelfPlay),
+        Some("train-q") => parse_q_learning_config(&args[1..]),
+        Some("tune") => parse_tuning_config(&args[1..]),
+        Some("edit") => parse_editor_options(&args[1..]).map(Command::Edit),
+        Some("analyze-map") => parse_map_analysis(&args[1..]),
+        Some("puzzle") => parse_puzzle_config(&args[1..]).map(Command::Puzzle),
+        _ => parse_play_options(args).map(Command::Play),
+    }
+}
+
+/// Load the evaluation weights of the file following a flag
+fn parse_weights_file<'a>(
+    flag: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<EvalWeights, String> {
+    let path = PathBuf::from(parse_flag_value::<String>(flag, args)?);
+    EvalWeights::load(&path).map_err(|err| format!("Can't load weights {}: {err}", path.display()))
+}
+
+/// Load the rules of the file following a flag
+fn parse_rules_file<'a>(
+    flag: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<RuleSet, String> {
+    let path = PathBuf::from(parse_flag_value::<String>(flag, args)?);
+    RuleSet::load(&path).map_err(|err| format!("Can't load rules {}: {err}", path.display()))
+}
+
+/// Parse the number of players following a flag, 2 to `MAX_PLAYERS`
+fn parse_player_count<'a>(
+    flag: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<usize, String> {
+    let players = parse_flag_value(flag, args)?;
+    if !(2..=MAX_PLAYERS).contains(&players) {
+        return Err(format!("{flag} must be 2 to {MAX_PLAYERS}"));
+    }
+    Ok(players)
+}
+
+/// Parse the board size following a flag, `MIN_BOARD_SIZE` to `MAX_BOARD_SIZE`
+fn parse_board_size<'a>(
+    flag: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<usize, String> {
+    let size = parse_flag_value(flag, args)?;
+    if !(MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&size) {
+        return Err(format!("{flag} must be {MIN_BOARD_SIZE} to {MAX_BOARD_SIZE}"));
+    }
+    Ok(size)
+}
+
+/// Parse the grid following a flag: `square`, `octile` or `hex`
+fn parse_grid<'a>(
+    flag: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<GridKind, String> {
+    let value: String = parse_flag_value(flag, args)?;
+    match value.as_str() {
+        "square" => Ok(GridKind::Square),
+        "octile" => Ok(GridKind::Octile),
+        "hex" => Ok(GridKind::Hex),
+        _ => Err(format!("Unknown grid {value}")),
+    }
+}
+
+/// Parse the map generator following a flag: `random`, `caves`, `maze` or `rooms`
+fn parse_generator<'a>(
+    flag: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<MapRandom, String> {
+    let value: String = parse_flag_value(flag, args)?;
+    match value.as_str() {
+        "random" => Ok(MapRandom::FullyRandom),
+        "caves" => Ok(MapRandom::Caves),
+        "maze" => Ok(MapRandom::Maze),
+        "rooms" => Ok(MapRandom::Rooms),
+        _ => Err(format!("Unknown generator {value}")),
+    }
+}
+
+/// Parse the value following a flag
+fn parse_flag_value<'a, T: FromStr>(
+    flag: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<T, String> {
+    let value = args.next().ok_or(format!("{flag} needs a value"))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value {value} for {flag}"))
+}
+
+///
+/// Parse the options of a CLI game
+///
+/// # Parameters
+///
+/// * args: `--analysis`, `--depth N`, `--size N`, `--players N`, `--teams`, `--simultaneous`,
+///   `--fog`, `--grid square|octile|hex`, `--wrap`, `--map <name|file>`,
+///   `--generator random|caves|maze|rooms`, `--rules <file>`, `--weights <file>` and `--opponent minimax|mcts|q:<table file>` are supported
+///
+fn parse_play_options(args: &[String]) -> Result<PlayOptions, String> {
+    let mut options = PlayOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--analysis" => options.analysis_mode = true,
+            "--depth" => options.search_depth = parse_flag_value(arg, &mut args)?,
+            "--size" => options.rules.board.size = parse_board_size(arg, &mut args)?,
+            "--players" => options.rules.board.players = parse_player_count(arg, &mut args)?,
+            "--teams" => options.rules.board.teams = true,
+            "--simultaneous" => options.rules.turns.simultaneous = true,
+            "--fog" => options.rules.fog.enabled = true,
+            "--grid" => options.rules.board.grid = parse_grid(arg, &mut args)?,
+            "--wrap" => options.rules.board.wrap = true,
+            "--map" => options.map = Some(find_map(&parse_flag_value::<String>(arg, &mut args)?)?),
+            "--generator" => options.map_randomness = parse_generator(arg, &mut args)?,
+            "--rules" => options.rules = parse_rules_file(arg, &mut args)?,
+            "--weights" => options.eval_weights = parse_weights_file(arg, &mut args)?,
+            "--opponent" => {
+                let value: String = parse_flag_value(arg, &mut args)?;
+                options.opponent = Some(match value.as_str() {
+                    "minimax" => OpponentKind::Minimax,
+                    "mcts" => OpponentKind::Mcts,
+                    _ => match value.strip_prefix("q:") {
+                        Some(path) => OpponentKind::QLearning(PathBuf::from(path)),
+                        None => return Err(format!("Unknown opponent {value}")),
+                    },
+                });
+            }
+            _ => return Err(format!("Unknown argument {arg}")),
+        }
+    }
+    if options.rules.board.teams && !options.rules.board.players.is_multiple_of(2) {
+        return Err("--teams needs an even number of players".to_string());
+    }
+    Ok(options)
+}
+
+///
+/// Parse the options of the self-play data generation
+///
+/// # Parameters
+///
+/// * args: `--games`, `--threads`, `--seed`, `--shard-size`, `--size`, `--players`,
+///   `--rules`, `--simulations` and `--out` are supported
+///
+fn parse_selfplay_config(args: &[String]) -> Result<SelfPlayConfig, String> {
+    let mut config = SelfPlayConfig::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => config.games = parse_flag_value(arg, &mut args)?,
+            "--threads" => config.threads = parse_flag_value(arg, &mut args)?,
+            "--seed" => config.seed = parse_flag_value(arg, &mut args)?,
+            "--shard-size" => config.shard_size = parse_flag_value(arg, &mut args)?,
+            "--size" => config.rules.board.size = parse_board_size(arg, &mut args)?,
+            "--players" => config.rules.board.players = parse_player_count(arg, &mut args)?,
+            "--rules" => config.rules = parse_rules_file(arg, &mut args)?,
+            "--simulations" => config.mcts.simulations = parse_flag_value(arg, &mut args)?,
+            "--out" => {
+                config.output_dir = PathBuf::from(parse_flag_value::<String>(arg, &mut args)?)
+            }
+            _ => return Err(format!("Unknown argument {arg}")),
+        }
+    }
+    Ok(config)
+}
+
+///
+/// Parse the options of the Q-learning training
+///
+/// # Parameters
+///
+/// * args: `--episodes`, `--size`, `--rules`, `--maps`, `--seed` and `--out` are supported
+///
+fn parse_q_learning_config(args: &[String]) -> Result<Command, String> {
+    let mut config = QLearningConfig::default();
+    let mut output = PathBuf::from("q_table.txt");
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--episodes" => config.episodes = parse_flag_value(arg, &mut args)?,
+            "--size" => config.rules.board.size = parse_board_size(arg, &mut args)?,
+            "--rules" => config.rules = parse_rules_file(arg, &mut args)?,
+            "--maps" => config.map_pool = parse_flag_value(arg, &mut args)?,
+            "--seed" => config.seed = parse_flag_value(arg, &mut args)?,
+            "--out" => output = PathBuf::from(parse_flag_value::<String>(arg, &mut args)?),
+            _ => return Err(format!("Unknown argument {arg}")),
+        }
+    }
+    Ok(Command::TrainQ(config, output))
+}
+
+///
+/// Parse the options of the evaluation tuning
+///
+/// # Parameters
+///
+/// * args: `--iterations`, `--games`, `--depth`, `--size`, `--rules`, `--seed`,
+///   `--weights` (starting weights) and `--out` are supported
+///
+fn parse_tuning_config(args: &[String]) -> Result<Command, String> {
+    let mut config = TuningConfig::default();
+    let mut output = PathBuf::from("weights.toml");
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--iterations" => config.iterations = parse_flag_value(arg, &mut args)?,
+            "--games" => config.games_per_iteration = parse_flag_value(arg, &mut args)?,
+            "--depth" => config.search_depth = parse_flag_value(arg, &mut args)?,
+            "--size" => config.rules.board.size = parse_board_size(arg, &mut args)?,
+            "--rules" => config.rules = parse_rules_file(arg, &mut args)?,
+            "--seed" => config.seed = parse_flag_value(arg, &mut args)?,
+            "--weights" => config.initial_weights = parse_weights_file(arg, &mut args)?,
+            "--out" => output = PathBuf::from(parse_flag_value::<String>(arg, &mut args)?),
+            _ => return Err(format!("Unknown argument {arg}")),
+        }
+    }
+    Ok(Command::Tune(config, output))
+}
+
+///
+/// Parse the options of the map editor
+///
+/// # Parameters
+///
+/// * args: the map (built-in name or file, created if it doesn't exist),
+///   `--size N` of a new map, `--rules <file>`, `--grid square|octile|hex` and `--wrap` are supported
+///
+fn parse_editor_options(args: &[String]) -> Result<EditorOptions, String> {
+    let mut options = EditorOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--size" => options.size = parse_board_size(arg, &mut args)?,
+            "--rules" => options.rules = parse_rules_file(arg, &mut args)?,
+            "--grid" => options.rules.board.grid = parse_grid(arg, &mut args)?,
+            "--wrap" => options.rules.board.wrap = true,
+            flag if flag.starts_with("--") => return Err(format!("Unknown argument {arg}")),
+            map => {
+                // a built-in map is saved to a file of its name
+                options.path = PathBuf::from(map);
+                options.map = get_builtin_map(map);
+                if options.map.is_some() {
+                    options.path = PathBuf::from(format!("{map}.txt"));
+                } else if options.path.exists() {
+                    let map = GameMap::load(&options.path)
+                        .map_err(|err| format!("Can't load map {}: {err}", options.path.display()))?;
+                    options.map = Some(map);
+                }
+            }
+        }
+    }
+    Ok(options)
+}
+
+///
+/// Parse the options of the map fairness analysis
+///
+/// # Parameters
+///
+/// * args: the maps (built-in names or files, every built-in map if none),
+///   `--random N` random maps of the rules (`--generator` picks their generator), `--games`, `--simulations`, `--threshold`,
+///   `--seed`, `--size`, `--players` and `--rules` are supported
+///
+fn parse_map_analysis(args: &[String]) -> Result<Command, String> {
+    let mut config = FairnessConfig::default();
+    let mut map_names: Vec<String> = Vec::new();
+    let mut random_maps = 0_u64;
+    let mut map_randomness = MapRandom::FullyRandom;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--random" => random_maps = parse_flag_value(arg, &mut args)?,
+            "--generator" => map_randomness = parse_generator(arg, &mut args)?,
+            "--games" => config.games = parse_flag_value(arg, &mut args)?,
+            "--simulations" => config.simulations = parse_flag_value(arg, &mut args)?,
+            "--threshold" => config.bias_threshold = parse_flag_value(arg, &mut args)?,
+            "--seed" => config.seed = parse_flag_value(arg, &mut args)?,
+            "--size" => config.rules.board.size = parse_board_size(arg, &mut args)?,
+            "--players" => config.rules.board.players = parse_player_count(arg, &mut args)?,
+            "--rules" => config.rules = parse_rules_file(arg, &mut args)?,
+            flag if flag.starts_with("--") => return Err(format!("Unknown argument {arg}")),
+            map => map_names.push(map.to_string()),
+        }
+    }
+
+    let mut maps = map_names
+        .iter()
+        .map(|name| find_map(name))
+        .collect::<Result<Vec<GameMap>, String>>()?;
+    for map_seed in config.seed..config.seed + random_maps {
+        let state = GameState::new_with_rules(&config.rules, map_randomness, map_seed);
+        maps.push(GameMap::from_state(&format!("random seed {map_seed}"), &state));
+    }
+    if maps.is_empty() {
+        maps = get_builtin_map_names()
+            .into_iter()
+            .filter_map(get_builtin_map)
+            .collect();
+    }
+    Ok(Command::AnalyzeMaps(config, maps))
+}
+
+///
+/// Parse the options of the puzzle mode
+///
+/// # Parameters
+///
+/// * args: `--goal atk|trap`, `--difficulty easy|medium|hard`, `--moves N` (most moves of the solution),
+///   `--atk N` (ATK to collect), `--seed`, `--size`, `--generator`, `--grid`, `--wrap` and `--rules` are supported.
+///   Without `--seed` the puzzle is random
+///
+fn parse_puzzle_config(args: &[String]) -> Result<PuzzleConfig, String> {
+    let mut config = PuzzleConfig {
+        seed: rand::random(),
+        ..PuzzleConfig::default()
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--goal" => {
+                let value: String = parse_flag_value(arg, &mut args)?;
+                config.goal = match value.as_str() {
+                    "atk" => Some(GoalKind::CollectAtk),
+                    "trap" => Some(GoalKind::TrapOpponent),
+                    _ => return Err(format!("Unknown goal {value}")),
+                };
+            }
+            "--difficulty" => {
+                let value: String = parse_flag_value(arg, &mut args)?;
+                config.difficulty = match value.as_str() {
+                    "easy" => Some(Difficulty::Easy),
+                    "medium" => Some(Difficulty::Medium),
+                    "hard" => Some(Difficulty::Hard),
+                    _ => return Err(format!("Unknown difficulty {value}")),
+                };
+            }
+            "--moves" => config.max_moves = parse_flag_value(arg, &mut args)?,
+            "--atk" => config.atk_gain = parse_flag_value(arg, &mut args)?,
+            "--seed" => config.seed = parse_flag_value(arg, &mut args)?,
+            "--size" => config.rules.board.size = parse_board_size(arg, &mut args)?,
+            "--generator" => config.map_randomness = parse_generator(arg, &mut args)?,
+            "--grid" => config.rules.board.grid = parse_grid(arg, &mut args)?,
+            "--wrap" => config.rules.board.wrap = true,
+            "--rules" => config.rules = parse_rules_file(arg, &mut args)?,
+            _ => return Err(format!("Unknown argument {arg}")),
+        }
+    }
+    config.min_moves = config.min_moves.min(config.max_moves);
+    Ok(config)
+}
@@ -0,0 +1,276 @@
+//! Editor Module
+//!
+//! Map editor on the terminal: move a cursor over the grid, cycle the tiles,
+//! place the start positions, check the map and save it in the map format
+//! (see the Map Module).
+//!
+use std::path::PathBuf;
+
+use strum::IntoEnumIterator;
+
+use crate::map::{get_elem_of_char, GameMap};
+use crate::map_analysis::check_map;
+use crate::rules::{RuleSet, MAX_BOARD_SIZE, MIN_BOARD_SIZE};
+use crate::state_repr::{
+    get_start_positions, transform_enum_elem_to_char, GameElements, MAX_PLAYERS, PLAYER_ELEMENTS,
+};
+use crate::topology::Topology;
+
+/// Options of the editor
+#[derive(Debug, Clone)]
+pub struct EditorOptions {
+    /// File of the map, created on save if it doesn't exist
+    pub path: PathBuf,
+    /// Map to edit, None for a new map
+    pub map: Option<GameMap>,
+    /// Size of a new map
+    pub size: usize,
+    /// Rules used by the checks (grid, wrap...)
+    pub rules: RuleSet,
+}
+
+impl Default for EditorOptions {
+    fn default() -> Self {
+        EditorOptions {
+            path: PathBuf::from("map.txt"),
+            map: None,
+            size: 5,
+            rules: RuleSet::default(),
+        }
+    }
+}
+
+/// Map being edited and the cursor on it
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapEditor {
+    pub map: GameMap,
+    /// x, y of the cursor
+    pub cursor: (usize, usize),
+}
+
+/// Tiles the cursor can cycle through, players excluded
+pub fn get_editable_tiles() -> Vec<GameElements> {
+    GameElements::iter()
+        .filter(|elem| !PLAYER_ELEMENTS.contains(elem))
+        .collect()
+}
+
+impl MapEditor {
+    pub fn new(map: GameMap) -> MapEditor {
+        MapEditor { map, cursor: (0, 0) }
+    }
+
+    /// Empty passable map with two players in their usual start positions
+    pub fn new_empty(name: &str, size: usize) -> MapEditor {
+        let size = size.clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE);
+        MapEditor::new(GameMap {
+            name: name.to_string(),
+            tiles: vec![vec![GameElements::Passable; size]; size],
+            starts: get_start_positions(size, 2, false),
+        })
+    }
+
+    /// Move the cursor, it stops at the edges
+    pub fn move_cursor(&mut self, offset: (i8, i8), steps: usize) {
+        // more steps than the size always end on the edge
+        let steps = steps.min(self.map.get_size()) as i64;
+        let last = self.map.get_size() as i64 - 1;
+        let x = self.cursor.0 as i64 + offset.0 as i64 * steps;
+        let y = self.cursor.1 as i64 + offset.1 as i64 * steps;
+        self.cursor = (x.clamp(0, last) as usize, y.clamp(0, last) as usize);
+    }
+
+    /// Player starting under the cursor
+    fn get_start_on_cursor(&self) -> Option<usize> {
+        self.map.starts.iter().position(|start| *start == self.cursor)
+    }
+
+    ///
+    /// Change the tile under the cursor.
+    /// The tile under a start position stays passable.
+    ///
+    /// # Returns
+    ///
+    /// Why the tile can't be changed
+    ///
+    pub fn set_tile(&mut self, elem: GameElements) -> Result<(), String> {
+        if PLAYER_ELEMENTS.contains(&elem) {
+            return Err("Place players with their number".to_string());
+        }
+        if let Some(player) = self.get_start_on_cursor() {
+            return Err(format!("P{} starts here, move it first", player + 1));
+        }
+        self.map.tiles[self.cursor.0][self.cursor.1] = elem;
+        Ok(())
+    }
+
+    /// Next (or previous) tile of `get_editable_tiles` under the cursor
+    pub fn cycle_tile(&mut self, forward: bool) -> Result<(), String> {
+        let tiles = get_editable_tiles();
+        let current = self.map.tiles[self.cursor.0][self.cursor.1];
+        let idx = tiles.iter().position(|elem| *elem == current).unwrap_or(0);
+        let next = if forward {
+            (idx + 1) % tiles.len()
+        } else {
+            (idx + tiles.len() - 1) % tiles.len()
+        };
+        self.set_tile(tiles[next])
+    }
+
+    ///
+    /// Put the start of the player under the cursor (moved if already placed).
+    /// Players are added in order: player 3 needs players 1 and 2.
+    ///
+    pub fn place_start(&mut self, player: usize) -> Result<(), String> {
+        if player >= MAX_PLAYERS || player > self.map.starts.len() {
+            return Err(format!("Place P{} first", self.map.starts.len() + 1));
+        }
+        if let Some(other) = self.get_start_on_cursor() {
+            return Err(format!("P{} starts here already", other + 1));
+        }
+        self.map.tiles[self.cursor.0][self.cursor.1] = GameElements::Passable;
+        if player == self.map.starts.len() {
+            self.map.starts.push(self.cursor);
+        } else {
+            self.map.starts[player] = self.cursor;
+        }
+        Ok(())
+    }
+
+    /// Remove the start of the last player, a map keeps two players
+    pub fn remove_last_start(&mut self) -> Result<(), String> {
+        if self.map.starts.len() <= 2 {
+            return Err("A map needs two players".to_string());
+        }
+        self.map.starts.pop();
+        Ok(())
+    }
+
+    /// Print the map, the cursor tile between brackets
+    pub fn print(&self, topology: &Topology) {
+        let size = self.map.get_size();
+        for y in 0..size {
+            let mut collect_str = topology.get_row_indent(y);
+            for x in 0..size {
+                let elem = match self.map.starts.iter().position(|start| *start == (x, y)) {
+                    Some(player) => PLAYER_ELEMENTS[player],
+                    None => self.map.tiles[x][y],
+                };
+                let on_cursor = self.cursor == (x, y);
+                let after_cursor = x > 0 && self.cursor == (x - 1, y);
+                collect_str.push(match (on_cursor, after_cursor) {
+                    (true, _) => '[',
+                    (_, true) => ']',
+                    _ => ' ',
+                });
+                collect_str.push(transform_enum_elem_to_char(&elem));
+            }
+            if self.cursor == (size - 1, y) {
+                collect_str.push(']');
+            }
+            println!("{}", collect_str);
+        }
+        let tile = self.map.tiles[self.cursor.0][self.cursor.1];
+        println!("CURSOR {},{}: {}", self.cursor.0, self.cursor.1, tile);
+    }
+}
+
+/// Offset of a cursor move typed by the user
+fn translate_cursor_move(move_inp: &str) -> Option<(i8, i8)> {
+    match move_inp {
+        "u" => Some((0, -1)),
+        "d" => Some((0, 1)),
+        "l" => Some((-1, 0)),
+        "r" => Some((1, 0)),
+        _ => None,
+    }
+}
+
+/// Print the commands of the editor
+fn print_editor_help() {
+    println!("Commands:");
+    println!("u, d, l, r: move the cursor (`r 3` moves 3 tiles)");
+    println!("n, p: next / previous tile");
+    println!("t <char>: set the tile by its character (`t x`, `t .` for the void)");
+    println!("1 to {}: place the start of the player", MAX_PLAYERS);
+    println!("del: remove the start of the last player");
+    println!("check: connectivity and fairness checks");
+    println!("w [file]: save the map");
+    println!("help: show the commands");
+    println!("q: quit");
+}
+
+/// Start the map editor on CLI
+pub fn start_map_editor(options: &EditorOptions) {
+    let name = options
+        .path
+        .file_stem()
+        .map_or("map".to_string(), |stem| stem.to_string_lossy().to_string());
+    let mut editor = match options.map.clone() {
+        Some(map) => MapEditor::new(map),
+        None => MapEditor::new_empty(&name, options.size),
+    };
+    let mut path = options.path.clone();
+    let mut rules = options.rules.clone();
+    print_editor_help();
+
+    loop {
+        rules.board.size = editor.map.get_size();
+        println!("====");
+        editor.print(&Topology::new(&rules.board));
+        println!("EDIT {}: ", editor.map.name);
+        let mut command = String::new();
+        std::io::stdin()
+            .read_line(&mut command)
+            .expect("Failed to read message");
+        if command.is_empty() {
+            return;
+        }
+        let mut words = command.split_whitespace();
+        let result = match (words.next(), words.next()) {
+            (Some("q"), _) => return,
+            (Some("help"), _) => {
+                print_editor_help();
+                Ok(())
+            }
+            (Some("n"), None) => editor.cycle_tile(true),
+            (Some("p"), None) => editor.cycle_tile(false),
+            (Some("t"), Some(tile)) => {
+                let elem = tile.chars().next().and_then(get_elem_of_char);
+                elem.ok_or(format!("Unknown tile {tile}"))
+                    .and_then(|elem| editor.set_tile(elem))
+            }
+            (Some("del"), None) => editor.remove_last_start(),
+            (Some("check"), None) => check_map(&editor.map, &rules).map(|check| check.print()),
+            (Some("w"), file) => {
+                if let Some(file) = file {
+                    path = PathBuf::from(file);
+                }
+                match editor.map.save(&path) {
+                    Ok(()) => {
+                        println!("Saved to {}", path.display());
+                        Ok(())
+                    }
+                    Err(err) => Err(format!("Can't save {}: {err}", path.display())),
+                }
+            }
+            (Some(word), steps) => match (translate_cursor_move(word), word.parse::<usize>()) {
+                (Some(offset), _) => match steps.map_or(Ok(1), str::parse) {
+                    Ok(steps) => {
+                        editor.move_cursor(offset, steps);
+                        Ok(())
+                    }
+                    Err(_) => Err(format!("Invalid number of tiles {}", steps.unwrap_or(""))),
+                },
+                (None, Ok(player)) if player >= 1 && steps.is_none() => {
+                    editor.place_start(player - 1)
+                }
+                _ => Err(format!("Unknown command {}", command.trim())),
+            },
+            (None, _) => Ok(()),
+        };
+        if let Err(message) = result {
+            println!("{message}");
+        }
+    }
+}
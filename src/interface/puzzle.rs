@@ -0,0 +1,116 @@
+//! Puzzle CLI Module
+//!
+//! Play a generated puzzle on the terminal (see the Puzzle Module).
+//! A failed try starts the puzzle over.
+//!
+use crate::action::get_legal_action;
+use crate::interface::cli::translate_move;
+use crate::puzzle::{generate_puzzle, play_puzzle_move, solve_puzzle, Puzzle, PuzzleConfig, PuzzleStep};
+
+/// Print the commands of the puzzle mode
+fn print_puzzle_help() {
+    println!("Commands:");
+    println!("u, l, r, d (ur, ul, dr, dl), s, a: your move, like in a game");
+    println!("hint: next winning move from here");
+    println!("solution: show the solution");
+    println!("retry: start the puzzle over");
+    println!("help: show the commands");
+    println!("q: quit");
+}
+
+/// Print the goal and the moves left
+fn print_goal(puzzle: &Puzzle, moves_left: u32) {
+    println!(
+        "GOAL: {} | MOVES LEFT {} of {}",
+        puzzle.goal.get_description(),
+        moves_left,
+        puzzle.max_moves
+    );
+}
+
+/// Generate a puzzle and play it on CLI
+pub fn start_puzzle_on_cli(config: &PuzzleConfig) {
+    let Some(puzzle) = generate_puzzle(config) else {
+        println!(
+            "No puzzle found in {} positions, try another seed or fewer constraints",
+            config.attempts
+        );
+        return;
+    };
+    println!(
+        "PUZZLE seed {} | {:?} ({} moves searched by the solver)",
+        config.seed, puzzle.difficulty, puzzle.nodes
+    );
+    println!(
+        "You are P{}, the opponent keeps the most room it can. Win in at most {} moves!",
+        puzzle.player + 1,
+        puzzle.max_moves
+    );
+    print_puzzle_help();
+
+    let mut state = puzzle.state.clone();
+    let mut moves_left = puzzle.max_moves;
+    loop {
+        println!("====");
+        state.print_pretty_state();
+        print_goal(&puzzle, moves_left);
+        let mut command = String::new();
+        std::io::stdin()
+            .read_line(&mut command)
+            .expect("Failed to read message");
+        if command.is_empty() {
+            return;
+        }
+        match command.trim() {
+            "q" => return,
+            "help" => print_puzzle_help(),
+            "solution" => println!("SOLUTION: {:?}", puzzle.solution),
+            "retry" => {
+                state = puzzle.state.clone();
+                moves_left = puzzle.max_moves;
+            }
+            "hint" => {
+                let search = solve_puzzle(&state, puzzle.player, &puzzle.goal, moves_left, 1);
+                match search.solutions.first().and_then(|line| line.first()) {
+                    Some(action) => println!("HINT: {:?}", action),
+                    None => println!("No win from here, `retry` to start over"),
+                }
+            }
+            user_inp => {
+                let action = translate_move(user_inp);
+                let Some(action) = action.filter(|action| {
+                    get_legal_action(&state, puzzle.player).contains(action)
+                }) else {
+                    println!("Illegal move {user_inp}");
+                    continue;
+                };
+                match play_puzzle_move(&state, puzzle.player, &puzzle.goal, &action) {
+                    PuzzleStep::Solved(final_state) => {
+                        final_state.print_pretty_state();
+                        let moves = puzzle.max_moves - moves_left + 1;
+                        println!("SOLVED in {} moves!", moves);
+                        return;
+                    }
+                    PuzzleStep::Failed(final_state) => {
+                        final_state.print_pretty_state();
+                        println!("FAILED: the game is over or you are stuck, back to the start");
+                        state = puzzle.state.clone();
+                        moves_left = puzzle.max_moves;
+                    }
+                    PuzzleStep::Going(next_state, answer) => {
+                        if let Some(answer) = answer {
+                            println!("OPPONENT PLAYS {:?}", answer);
+                        }
+                        state = next_state;
+                        moves_left -= 1;
+                        if moves_left == 0 {
+                            println!("OUT OF MOVES: back to the start");
+                            state = puzzle.state.clone();
+                            moves_left = puzzle.max_moves;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Bonk Block
+//!
+//! Game engine, search and interfaces of the game.
+//! The binary only starts the CLI, everything else lives here
+//! so it can be used from Rust (bots, training, tools).
+//!
+pub mod state_repr;
+pub mod rules;
+pub mod skill;
+pub mod result;
+pub mod utils;
+pub mod action;
+pub mod interface;
+pub mod terminal;
+pub mod search;
+pub mod analysis;
+pub mod environment;
+pub mod selfplay;
+pub mod agent;
+pub mod game_loop;
+pub mod tuning;
+pub mod arena;
+pub mod respawn;
+pub mod fog;
+pub mod topology;
+pub mod map;
+pub mod map_analysis;
+pub mod map_generation;
+pub mod puzzle;
@@ -1,11 +1,11 @@
-mod state_repr;
-mod result;
-mod utils;
-mod action;
-mod interface;
-mod terminal;
-
-use interface::cli;
+use rust_game::agent::q_learning::train_q_table;
+use rust_game::interface::cli;
+use rust_game::interface::command::{parse_command, Command};
+use rust_game::interface::editor::start_map_editor;
+use rust_game::interface::puzzle::start_puzzle_on_cli;
+use rust_game::map_analysis::analyze_map_fairness;
+use rust_game::selfplay::run_selfplay;
+use rust_game::tuning::tune_eval_weights;
 
 
 /// Main function of the file
@@ -15,5 +15,50 @@ use interface::cli;
 /// None
 /// 
 fn main() {
-    cli::start_game_on_cli();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match parse_command(&args) {
+        Ok(Command::Play(options)) => cli::start_game_on_cli(&options),
+        Ok(Command::SelfPlay(config)) => match run_selfplay(&config) {
+            Ok(summary) => println!(
+                "Shards written: {} | skipped: {} | positions: {}",
+                summary.shards_written, summary.shards_skipped, summary.positions_written
+            ),
+            Err(err) => eprintln!("Self-play failed: {err}"),
+        },
+        Ok(Command::TrainQ(config, output)) => {
+            let table = train_q_table(&config);
+            match table.save(&output, &config.rules) {
+                Ok(()) => println!("{} states saved to {}", table.len(), output.display()),
+                Err(err) => eprintln!("Can't save Q-table: {err}"),
+            }
+        }
+        Ok(Command::Tune(config, output)) => {
+            let weights = tune_eval_weights(&config, |progress| {
+                println!(
+                    "ITERATION {} | RESULT +/- {:+.3} | {:?}",
+                    progress.iteration, progress.result, progress.weights
+                )
+            });
+            match weights.save(&output) {
+                Ok(()) => println!("Weights saved to {}", output.display()),
+                Err(err) => eprintln!("Can't save weights: {err}"),
+            }
+        }
+        Ok(Command::Edit(options)) => start_map_editor(&options),
+        Ok(Command::Puzzle(config)) => start_puzzle_on_cli(&config),
+        Ok(Command::AnalyzeMaps(config, maps)) => {
+            let mut flagged = 0;
+            for map in maps.iter() {
+                match analyze_map_fairness(map, &config) {
+                    Ok(report) => {
+                        report.print();
+                        flagged += usize::from(report.flagged);
+                    }
+                    Err(err) => eprintln!("Can't analyze map {}: {err}", map.name),
+                }
+            }
+            println!("{} of {} maps flagged (bias above {})", flagged, maps.len(), config.bias_threshold);
+        }
+        Err(message) => eprintln!("{message}"),
+    }
 }
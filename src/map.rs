@@ -0,0 +1,191 @@
+//! Map Module
+//!
+//! Hand-authored maps, written as text files:
+//!
+//! ```text
+//! # lines starting with `#` are comments
+//! . o 1 o .
+//! o + o - o
+//! o o x o o
+//! o - o + o
+//! . o 2 o .
+//! ```
+//!
+//! * one line per row (y), one character per tile (x), spaces are ignored
+//! * tiles use the characters of `transform_enum_elem_to_char`
+//! * `.` is the void: cells outside of an irregular board
+//! * `1` to `4` are the start positions of the players, on a passable tile
+//!
+//! A map that isn't square is padded with void.
+//! Some maps are built in, see `get_builtin_map_names`.
+//!
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use strum::IntoEnumIterator;
+
+use crate::rules::{MAX_BOARD_SIZE, MIN_BOARD_SIZE};
+use crate::state_repr::{
+    transform_enum_elem_to_char, GameElements, GameState, MAX_PLAYERS, PLAYER_ELEMENTS,
+};
+
+/// Character starting a comment line
+pub const COMMENT_CHAR: char = '#';
+
+/// Maps shipped with the game, by name
+const BUILTIN_MAPS: [(&str, &str); 5] = [
+    ("duel", include_str!("../maps/duel.txt")),
+    ("cross", include_str!("../maps/cross.txt")),
+    ("ring", include_str!("../maps/ring.txt")),
+    ("diamond", include_str!("../maps/diamond.txt")),
+    ("maze", include_str!("../maps/maze.txt")),
+];
+
+/// A hand-authored map
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameMap {
+    pub name: String,
+    /// Tiles by x then y, players excluded
+    pub tiles: Vec<Vec<GameElements>>,
+    /// Start position of each player, by player id
+    pub starts: Vec<(usize, usize)>,
+}
+
+impl GameMap {
+    /// Height and width of the map
+    pub fn get_size(&self) -> usize {
+        self.tiles.len()
+    }
+
+    ///
+    /// Read a map from its text
+    ///
+    /// # Parameters
+    ///
+    /// * name: Name of the map
+    /// * content: Text of the map
+    ///
+    /// # Returns
+    ///
+    /// The map, or a message explaining what is wrong
+    ///
+    pub fn parse(name: &str, content: &str) -> Result<GameMap, String> {
+        let rows: Vec<Vec<char>> = content
+            .lines()
+            .filter(|line| !line.trim_start().starts_with(COMMENT_CHAR))
+            .map(|line| line.chars().filter(|c| !c.is_whitespace()).collect::<Vec<char>>())
+            .filter(|row| !row.is_empty())
+            .collect();
+        let size = rows.iter().map(Vec::len).chain([rows.len()]).max().unwrap_or(0);
+        if size < MIN_BOARD_SIZE {
+            return Err(format!("Map {name} is too small"));
+        }
+        if size > MAX_BOARD_SIZE {
+            return Err(format!("Map {name} is bigger than {MAX_BOARD_SIZE} tiles"));
+        }
+
+        let mut tiles = vec![vec![GameElements::Void; size]; size];
+        let mut starts: Vec<Option<(usize, usize)>> = vec![None; MAX_PLAYERS];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, tile_char) in row.iter().enumerate() {
+                let elem = get_elem_of_char(*tile_char).ok_or(format!(
+                    "Unknown tile '{tile_char}' at {x},{y} in map {name}"
+                ))?;
+                match PLAYER_ELEMENTS.iter().position(|player| *player == elem) {
+                    Some(player) if starts[player].is_some() => {
+                        return Err(format!("Player {} starts twice in map {name}", player + 1));
+                    }
+                    Some(player) => {
+                        starts[player] = Some((x, y));
+                        tiles[x][y] = GameElements::Passable;
+                    }
+                    None => tiles[x][y] = elem,
+                }
+            }
+        }
+
+        // players 1, 2... without gap
+        let count = starts.iter().take_while(|start| start.is_some()).count();
+        if count < 2 || starts[count..].iter().any(Option::is_some) {
+            return Err(format!("Map {name} needs start positions 1, 2... without gap"));
+        }
+        Ok(GameMap {
+            name: name.to_string(),
+            tiles,
+            starts: starts.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Map of a state: its tiles and the current positions of the players
+    pub fn from_state(name: &str, state: &GameState) -> GameMap {
+        GameMap {
+            name: name.to_string(),
+            tiles: state.get_map_representation().clone(),
+            starts: state.get_player_positions(),
+        }
+    }
+
+    /// Read a map file, named after the file
+    pub fn load(path: &Path) -> io::Result<GameMap> {
+        let content = fs::read_to_string(path)?;
+        let name = path
+            .file_stem()
+            .map_or("map".to_string(), |stem| stem.to_string_lossy().to_string());
+        GameMap::parse(&name, &content).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Text of the map, the format read by `parse`
+    pub fn to_text(&self) -> String {
+        let size = self.get_size();
+        let mut text = String::new();
+        for y in 0..size {
+            let row: Vec<String> = (0..size)
+                .map(|x| {
+                    let elem = match self.starts.iter().position(|start| *start == (x, y)) {
+                        Some(player) => PLAYER_ELEMENTS[player],
+                        None => self.tiles[x][y],
+                    };
+                    transform_enum_elem_to_char(&elem).to_string()
+                })
+                .collect();
+            text.push_str(&row.join(" "));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Write the map to a text file
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+}
+
+/// Element of a map character, None for an unknown character
+pub fn get_elem_of_char(tile_char: char) -> Option<GameElements> {
+    GameElements::iter().find(|elem| transform_enum_elem_to_char(elem) == tile_char)
+}
+
+/// Names of the maps shipped with the game
+pub fn get_builtin_map_names() -> Vec<&'static str> {
+    BUILTIN_MAPS.iter().map(|(name, _)| *name).collect()
+}
+
+/// Map shipped with the game, None if no map has this name
+pub fn get_builtin_map(name: &str) -> Option<GameMap> {
+    let (name, content) = BUILTIN_MAPS.iter().find(|(map_name, _)| *map_name == name)?;
+    Some(GameMap::parse(name, content).expect("Built-in maps are valid"))
+}
+
+/// Built-in map of the name, or the map file at this path
+pub fn find_map(name_or_path: &str) -> Result<GameMap, String> {
+    if let Some(map) = get_builtin_map(name_or_path) {
+        return Ok(map);
+    }
+    GameMap::load(Path::new(name_or_path)).map_err(|err| {
+        format!(
+            "Can't load map {name_or_path}: {err} (built-in maps: {})",
+            get_builtin_map_names().join(", ")
+        )
+    })
+}
@@ -0,0 +1,336 @@
+//! Map Analysis Module
+//!
+//! Static checks of a map, before any game is played on it:
+//!
+//! * connectivity: every start position reaches the others, no passable tile is cut off
+//! * fairness: distance from each start to the closest bonus, and the
+//!   territory split (tiles each player reaches strictly first)
+//!
+//! Distances follow the grid of the rules and ignore the trails left by the moves.
+//!
+//! The fairness analysis adds the opening moves of each player and bot-vs-bot
+//! games where every bot plays every seat in turn: a fair map gives every seat
+//! about the same score. A map whose seat scores differ more than the bias
+//! threshold is flagged.
+//!
+use crate::action::get_legal_action;
+use crate::agent::base::Agent;
+use crate::agent::search_agent::MctsAgent;
+use crate::game_loop::play_headless_game;
+use crate::map::GameMap;
+use crate::rules::RuleSet;
+use crate::search::evaluation::get_distance_map;
+use crate::search::mcts::MctsConfig;
+use crate::skill::get_sp_skill_of_tile;
+use crate::state_repr::{is_passable_elem, GameElements, GameState};
+use crate::terminal::get_result_for_player;
+
+/// Largest difference of bonus distance between two players before a map is unfair
+pub const MAX_BONUS_DISTANCE_GAP: u16 = 1;
+
+/// Largest difference of territory between two players, as a share of the
+/// passable tiles, before a map is unfair
+pub const MAX_TERRITORY_GAP: f32 = 0.1;
+
+/// Result of the checks of a map
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapCheck {
+    /// Players whose start can't reach the start of every other player
+    pub isolated_players: Vec<u8>,
+    /// Passable tiles no player can reach
+    pub unreachable_tiles: usize,
+    /// Passable tiles of the map, starts included
+    pub passable_tiles: usize,
+    /// Distance from each start to its closest bonus or skill tile, None if there is none
+    pub bonus_distances: Vec<Option<u16>>,
+    /// Average distance from each start to the bonus and skill tiles it can reach
+    pub mean_bonus_distances: Vec<Option<f32>>,
+    /// Tiles each player reaches strictly before the others
+    pub territory: Vec<usize>,
+}
+
+impl MapCheck {
+    /// Whether every start reaches the others
+    pub fn is_connected(&self) -> bool {
+        self.isolated_players.is_empty()
+    }
+
+    /// Problems found by the checks, empty for a connected and fair map
+    pub fn get_issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        for player in self.isolated_players.iter() {
+            issues.push(format!("P{} can't reach every other player", player + 1));
+        }
+        if self.unreachable_tiles > 0 {
+            issues.push(format!("{} passable tiles can't be reached", self.unreachable_tiles));
+        }
+        let distances: Vec<u16> = self.bonus_distances.iter().flatten().copied().collect();
+        if distances.len() != self.bonus_distances.len() && !distances.is_empty() {
+            issues.push("Some players can't reach any bonus".to_string());
+        }
+        if let (Some(min), Some(max)) = (distances.iter().min(), distances.iter().max()) {
+            if max - min > MAX_BONUS_DISTANCE_GAP {
+                issues.push(format!("Closest bonus is {min} to {max} tiles away depending on the player"));
+            }
+        }
+        let (min, max) = (
+            self.territory.iter().min().copied().unwrap_or(0),
+            self.territory.iter().max().copied().unwrap_or(0),
+        );
+        if (max - min) as f32 > MAX_TERRITORY_GAP * self.passable_tiles as f32 {
+            issues.push(format!("Territory split from {min} to {max} tiles"));
+        }
+        issues
+    }
+
+    /// Print the checks and their issues
+    pub fn print(&self) {
+        for (player, (distance, territory)) in self
+            .bonus_distances
+            .iter()
+            .zip(self.territory.iter())
+            .enumerate()
+        {
+            let distance = distance.map_or("-".to_string(), |d| d.to_string());
+            println!(
+                "P{} | closest bonus {} | territory {}",
+                player + 1,
+                distance,
+                territory
+            );
+        }
+        let issues = self.get_issues();
+        if issues.is_empty() {
+            println!("CHECK OK: connected and fair");
+        }
+        for issue in issues {
+            println!("CHECK: {issue}");
+        }
+    }
+}
+
+/// Whether the tile gives ATK or a special skill
+pub fn is_bonus_elem(game_elem: &GameElements) -> bool {
+    *game_elem == GameElements::BonusATKBonusTile || get_sp_skill_of_tile(game_elem).is_some()
+}
+
+/// State at the start of a game on the map, one player on each start
+///
+/// # Parameters
+///
+/// * map: the map
+/// * rules: Rules of the game (grid, wrap...), the players come from the map
+pub fn get_map_state(map: &GameMap, rules: &RuleSet) -> Result<GameState, String> {
+    let mut rules = rules.clone();
+    rules.board.players = map.starts.len();
+    GameState::new_with_map(&rules, map, 0)
+}
+
+///
+/// Run the connectivity and fairness checks of a map
+///
+/// # Parameters
+///
+/// * map: the map
+/// * rules: Rules of the game (grid, wrap...)
+///
+/// # Returns
+///
+/// The checks, or a message if no game can be played on the map
+///
+pub fn check_map(map: &GameMap, rules: &RuleSet) -> Result<MapCheck, String> {
+    let state = get_map_state(map, rules)?;
+    let distances: Vec<Vec<Vec<Option<u16>>>> = map
+        .starts
+        .iter()
+        .map(|start| get_distance_map(&state, *start))
+        .collect();
+
+    let isolated_players = (0..map.starts.len())
+        .filter(|player| {
+            map.starts
+                .iter()
+                .any(|(x, y)| distances[*player][*x][*y].is_none())
+        })
+        .map(|player| player as u8)
+        .collect();
+
+    let mut unreachable_tiles = 0;
+    let mut passable_tiles = 0;
+    let mut bonus_distances = vec![None; map.starts.len()];
+    let mut bonus_sums = vec![(0_u32, 0_u32); map.starts.len()];
+    let mut territory = vec![0; map.starts.len()];
+    for (x, row) in state.get_map_representation().iter().enumerate() {
+        for (y, elem) in row.iter().enumerate() {
+            if !is_passable_elem(elem) {
+                continue;
+            }
+            passable_tiles += 1;
+            let tile_distances: Vec<Option<u16>> =
+                distances.iter().map(|distance| distance[x][y]).collect();
+            let Some(closest) = tile_distances.iter().flatten().min() else {
+                unreachable_tiles += 1;
+                continue;
+            };
+            let closest_players: Vec<usize> = (0..tile_distances.len())
+                .filter(|player| tile_distances[*player] == Some(*closest))
+                .collect();
+            if let [player] = closest_players[..] {
+                territory[player] += 1;
+            }
+            if is_bonus_elem(elem) {
+                for (player, distance) in tile_distances.iter().enumerate() {
+                    if let Some(distance) = distance {
+                        bonus_sums[player].0 += u32::from(*distance);
+                        bonus_sums[player].1 += 1;
+                    }
+                    bonus_distances[player] = match (bonus_distances[player], *distance) {
+                        (Some(best), Some(distance)) => Some(distance.min(best)),
+                        (best, None) => best,
+                        (None, distance) => distance,
+                    };
+                }
+            }
+        }
+    }
+
+    let mean_bonus_distances = bonus_sums
+        .iter()
+        .map(|(sum, count)| (*count > 0).then(|| *sum as f32 / *count as f32))
+        .collect();
+    Ok(MapCheck {
+        isolated_players,
+        unreachable_tiles,
+        passable_tiles,
+        bonus_distances,
+        mean_bonus_distances,
+        territory,
+    })
+}
+
+/// Settings of the fairness analysis
+#[derive(Debug, Clone)]
+pub struct FairnessConfig {
+    /// Rules of the games, the players come from the maps
+    pub rules: RuleSet,
+    /// Games played on each map, the bots change seats every game
+    pub games: u32,
+    /// Simulations of the MCTS bots
+    pub simulations: u32,
+    /// Largest gap between the best and the worst seat score before a map is flagged
+    pub bias_threshold: f32,
+    pub seed: u64,
+}
+
+impl Default for FairnessConfig {
+    fn default() -> Self {
+        FairnessConfig {
+            rules: RuleSet::default(),
+            games: 20,
+            simulations: 100,
+            bias_threshold: 0.2,
+            seed: 0,
+        }
+    }
+}
+
+/// Fairness of a map
+#[derive(Debug, Clone, PartialEq)]
+pub struct FairnessReport {
+    pub name: String,
+    /// Connectivity, bonus distances and territory split
+    pub check: MapCheck,
+    /// Legal actions of each player at the start
+    pub opening_moves: Vec<usize>,
+    /// Average score of each seat over the games: 1 win, 0.5 draw, 0 loss
+    pub seat_scores: Vec<f32>,
+    /// Best seat score minus the worst one
+    pub bias: f32,
+    /// The bias is above the threshold
+    pub flagged: bool,
+}
+
+impl FairnessReport {
+    /// Print the report of the map
+    pub fn print(&self) {
+        println!("MAP {}", self.name);
+        for (player, opening_moves) in self.opening_moves.iter().enumerate() {
+            let mean = self.check.mean_bonus_distances[player]
+                .map_or("-".to_string(), |mean| format!("{:.1}", mean));
+            let closest = self.check.bonus_distances[player]
+                .map_or("-".to_string(), |closest| closest.to_string());
+            println!(
+                "P{} | bonus distance closest {} mean {} | territory {} | opening moves {} | seat score {:.2}",
+                player + 1,
+                closest,
+                mean,
+                self.check.territory[player],
+                opening_moves,
+                self.seat_scores[player]
+            );
+        }
+        for issue in self.check.get_issues() {
+            println!("CHECK: {issue}");
+        }
+        let verdict = if self.flagged { "FLAGGED" } else { "OK" };
+        println!("BIAS {:.2} | {}", self.bias, verdict);
+    }
+}
+
+///
+/// Analyze the fairness of a map
+///
+/// # Parameters
+///
+/// * map: the map
+/// * config: Analysis settings
+///
+/// # Returns
+///
+/// The report, or a message if no game can be played on the map
+///
+pub fn analyze_map_fairness(map: &GameMap, config: &FairnessConfig) -> Result<FairnessReport, String> {
+    let check = check_map(map, &config.rules)?;
+    let state = get_map_state(map, &config.rules)?;
+    let player_count = state.get_player_count();
+    let opening_moves = (0..player_count as u8)
+        .map(|player| get_legal_action(&state, player).len())
+        .collect();
+
+    // bot `idx` plays seat `(idx + game) % player_count`
+    let mut seat_totals = vec![0.0; player_count];
+    for game in 0..config.games {
+        let mcts = MctsConfig {
+            simulations: config.simulations,
+            ..MctsConfig::default()
+        };
+        let game_seed = config.seed + u64::from(game) * player_count as u64;
+        let mut bots: Vec<MctsAgent> = (0..player_count)
+            .map(|idx| MctsAgent::new(mcts, game_seed + idx as u64))
+            .collect();
+        bots.rotate_right(game as usize % player_count);
+        let mut agents: Vec<&mut dyn Agent> =
+            bots.iter_mut().map(|bot| bot as &mut dyn Agent).collect();
+        let (final_state, _) = play_headless_game(&state, &mut agents);
+        for (seat, total) in seat_totals.iter_mut().enumerate() {
+            let result = get_result_for_player(&final_state, seat as u8);
+            *total += (f32::from(result) + 1.0) / 2.0;
+        }
+    }
+
+    let seat_scores: Vec<f32> = seat_totals
+        .iter()
+        .map(|total| total / config.games.max(1) as f32)
+        .collect();
+    let best = seat_scores.iter().copied().fold(f32::MIN, f32::max);
+    let worst = seat_scores.iter().copied().fold(f32::MAX, f32::min);
+    let bias = best - worst;
+    Ok(FairnessReport {
+        name: map.name.clone(),
+        check,
+        opening_moves,
+        seat_scores,
+        bias,
+        flagged: bias > config.bias_threshold,
+    })
+}
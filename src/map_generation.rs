@@ -0,0 +1,291 @@
+//! Map Generation Module
+//!
+//! Layouts of the random maps with more structure than `MapRandom::FullyRandom`:
+//!
+//! * `caves`: cellular automata, random walls smoothed into caves
+//! * `maze`: recursive backtracker maze, some inner walls knocked down to make loops
+//! * `rooms`: rooms joined by corridors
+//!
+//! The generators only place the walls, the open tiles are drawn afterwards with
+//! the generation weights. The start positions are always open and every open
+//! tile reaches them (4 neighbours, so on every grid).
+//! Settings come from `GenerationRules`, the draws from the map seed.
+//!
+use std::collections::VecDeque;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::rules::GenerationRules;
+use crate::state_repr::MapRandom;
+
+/// Steps to the 4 neighbours of a tile
+const SQUARE_STEPS: [(i64, i64); 4] = [(0, -1), (0, 1), (1, 0), (-1, 0)];
+
+/// Wall grid by x then y: true for a wall
+pub type WallGrid = Vec<Vec<bool>>;
+
+///
+/// Walls of a generated map
+///
+/// # Parameters
+///
+/// * height_width: Height and Width of the map
+/// * map_randomness: the generator, `FullyRandom` gives no wall
+/// * generation: settings of the generators
+/// * starts: start positions, left open and connected
+/// * rng: Random generator of the map
+///
+pub fn generate_walls(
+    height_width: usize,
+    map_randomness: MapRandom,
+    generation: &GenerationRules,
+    starts: &[(usize, usize)],
+    rng: &mut impl Rng,
+) -> WallGrid {
+    let mut walls = match map_randomness {
+        MapRandom::FullyRandom => vec![vec![false; height_width]; height_width],
+        MapRandom::Caves => generate_cave_walls(height_width, generation, rng),
+        MapRandom::Maze => generate_maze_walls(height_width, generation, rng),
+        MapRandom::Rooms => generate_room_walls(height_width, generation, rng),
+    };
+    for (x, y) in starts.iter() {
+        walls[*x][*y] = false;
+    }
+    connect_open_regions(&mut walls, starts);
+    walls
+}
+
+/// Neighbour one step away, None out of the map
+fn get_step(walls: &WallGrid, coor: (usize, usize), step: (i64, i64)) -> Option<(usize, usize)> {
+    let size = walls.len() as i64;
+    let (x, y) = (coor.0 as i64 + step.0, coor.1 as i64 + step.1);
+    ((0..size).contains(&x) && (0..size).contains(&y)).then_some((x as usize, y as usize))
+}
+
+/// Walls among the 8 tiles around, out of the map counts as a wall
+fn count_wall_neighbours(walls: &WallGrid, x: usize, y: usize) -> usize {
+    let mut count = 0;
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            if (dx, dy) == (0, 0) {
+                continue;
+            }
+            count += match get_step(walls, (x, y), (dx, dy)) {
+                Some((nx, ny)) => usize::from(walls[nx][ny]),
+                None => 1,
+            };
+        }
+    }
+    count
+}
+
+///
+/// Caves: `cave_fill` percent of walls, then `cave_steps` smoothing steps.
+/// A tile with more than 4 walls around becomes a wall, less than 4 becomes open.
+///
+fn generate_cave_walls(height_width: usize, generation: &GenerationRules, rng: &mut impl Rng) -> WallGrid {
+    let fill = generation.cave_fill.min(100);
+    let mut walls: WallGrid = (0..height_width)
+        .map(|_| (0..height_width).map(|_| rng.gen_range(0..100) < fill).collect())
+        .collect();
+    for _ in 0..generation.cave_steps {
+        walls = (0..height_width)
+            .map(|x| {
+                (0..height_width)
+                    .map(|y| match count_wall_neighbours(&walls, x, y) {
+                        count if count > 4 => true,
+                        count if count < 4 => false,
+                        _ => walls[x][y],
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+    walls
+}
+
+///
+/// Maze: the cells are the tiles with even x and y, a recursive backtracker
+/// opens the tiles between them. Then each inner wall between two open
+/// tiles is knocked down with a chance of `maze_loops` percent.
+///
+fn generate_maze_walls(height_width: usize, generation: &GenerationRules, rng: &mut impl Rng) -> WallGrid {
+    let mut walls = vec![vec![true; height_width]; height_width];
+    let cells = height_width.div_ceil(2);
+    let first = (rng.gen_range(0..cells) * 2, rng.gen_range(0..cells) * 2);
+    walls[first.0][first.1] = false;
+    let mut stack = vec![first];
+    while let Some(&cell) = stack.last() {
+        let next: Vec<(usize, usize)> = SQUARE_STEPS
+            .iter()
+            .filter_map(|(dx, dy)| get_step(&walls, cell, (dx * 2, dy * 2)))
+            .filter(|(x, y)| walls[*x][*y])
+            .collect();
+        match next.choose(rng) {
+            Some(&(x, y)) => {
+                walls[(cell.0 + x) / 2][(cell.1 + y) / 2] = false;
+                walls[x][y] = false;
+                stack.push((x, y));
+            }
+            None => {
+                stack.pop();
+            }
+        }
+    }
+
+    let loops = generation.maze_loops.min(100);
+    for x in 0..height_width {
+        for y in 0..height_width {
+            // walls between two cells, in a row or in a column
+            let between = [((x.wrapping_sub(1), y), (x + 1, y)), ((x, y.wrapping_sub(1)), (x, y + 1))];
+            let opens_loop = walls[x][y]
+                && (x + y) % 2 == 1
+                && between.iter().any(|(a, b)| {
+                    a.0 < height_width
+                        && a.1 < height_width
+                        && b.0 < height_width
+                        && b.1 < height_width
+                        && !walls[a.0][a.1]
+                        && !walls[b.0][b.1]
+                });
+            if opens_loop && rng.gen_range(0..100) < loops {
+                walls[x][y] = false;
+            }
+        }
+    }
+    walls
+}
+
+///
+/// Rooms: up to `room_count` rooms of `room_min_size` to `room_max_size` tiles
+/// a side, not overlapping, each joined to the previous one by a corridor.
+///
+fn generate_room_walls(height_width: usize, generation: &GenerationRules, rng: &mut impl Rng) -> WallGrid {
+    let mut walls = vec![vec![true; height_width]; height_width];
+    let max_size = generation.room_max_size.clamp(1, height_width);
+    let min_size = generation.room_min_size.clamp(1, max_size);
+    // x, y, width, height
+    let mut rooms: Vec<(usize, usize, usize, usize)> = Vec::new();
+    for _ in 0..generation.room_count * 10 {
+        if rooms.len() >= generation.room_count as usize {
+            break;
+        }
+        let width = rng.gen_range(min_size..=max_size);
+        let height = rng.gen_range(min_size..=max_size);
+        let x = rng.gen_range(0..=height_width - width);
+        let y = rng.gen_range(0..=height_width - height);
+        let overlaps = rooms.iter().any(|(rx, ry, rw, rh)| {
+            x < rx + rw + 1 && *rx < x + width + 1 && y < ry + rh + 1 && *ry < y + height + 1
+        });
+        if !overlaps {
+            rooms.push((x, y, width, height));
+        }
+    }
+
+    for (x, y, width, height) in rooms.iter() {
+        for column in walls.iter_mut().skip(*x).take(*width) {
+            for tile in column.iter_mut().skip(*y).take(*height) {
+                *tile = false;
+            }
+        }
+    }
+    let centers: Vec<(usize, usize)> = rooms
+        .iter()
+        .map(|(x, y, width, height)| (x + width / 2, y + height / 2))
+        .collect();
+    for pair in centers.windows(2) {
+        carve_corridor(&mut walls, pair[0], pair[1], rng.gen());
+    }
+    walls
+}
+
+/// Open an L-shaped corridor between two tiles
+fn carve_corridor(walls: &mut WallGrid, from: (usize, usize), to: (usize, usize), row_first: bool) {
+    let corner = if row_first { (to.0, from.1) } else { (from.0, to.1) };
+    for (a, b) in [(from, corner), (corner, to)] {
+        let (x_min, x_max) = (a.0.min(b.0), a.0.max(b.0));
+        let (y_min, y_max) = (a.1.min(b.1), a.1.max(b.1));
+        for column in walls.iter_mut().take(x_max + 1).skip(x_min) {
+            for tile in column.iter_mut().take(y_max + 1).skip(y_min) {
+                *tile = false;
+            }
+        }
+    }
+}
+
+/// Region of the open tiles connected to each tile, None for a wall
+fn get_open_regions(walls: &WallGrid) -> Vec<Vec<Option<usize>>> {
+    let size = walls.len();
+    let mut regions = vec![vec![None; size]; size];
+    let mut region_count = 0;
+    for x in 0..size {
+        for y in 0..size {
+            if walls[x][y] || regions[x][y].is_some() {
+                continue;
+            }
+            regions[x][y] = Some(region_count);
+            let mut queue = VecDeque::from([(x, y)]);
+            while let Some(coor) = queue.pop_front() {
+                for step in SQUARE_STEPS {
+                    if let Some((nx, ny)) = get_step(walls, coor, step) {
+                        if !walls[nx][ny] && regions[nx][ny].is_none() {
+                            regions[nx][ny] = Some(region_count);
+                            queue.push_back((nx, ny));
+                        }
+                    }
+                }
+            }
+            region_count += 1;
+        }
+    }
+    regions
+}
+
+///
+/// Open the walls on the shortest way from the region of the first start
+/// to every other open region, until all the open tiles are connected
+///
+pub fn connect_open_regions(walls: &mut WallGrid, starts: &[(usize, usize)]) {
+    let Some(&first) = starts.first() else {
+        return;
+    };
+    let size = walls.len();
+    loop {
+        let regions = get_open_regions(walls);
+        let main = regions[first.0][first.1];
+
+        // breadth first search from the main region through the walls
+        let mut parents: Vec<Vec<Option<(usize, usize)>>> = vec![vec![None; size]; size];
+        let mut queue: VecDeque<(usize, usize)> = (0..size)
+            .flat_map(|x| (0..size).map(move |y| (x, y)))
+            .filter(|(x, y)| regions[*x][*y] == main)
+            .collect();
+        for (x, y) in queue.iter() {
+            parents[*x][*y] = Some((*x, *y));
+        }
+        let mut reached = None;
+        while let Some(coor) = queue.pop_front() {
+            if regions[coor.0][coor.1].is_some() && regions[coor.0][coor.1] != main {
+                reached = Some(coor);
+                break;
+            }
+            for step in SQUARE_STEPS {
+                if let Some((nx, ny)) = get_step(walls, coor, step) {
+                    if parents[nx][ny].is_none() {
+                        parents[nx][ny] = Some(coor);
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+
+        let Some(mut coor) = reached else {
+            return;
+        };
+        while let Some(parent) = parents[coor.0][coor.1].filter(|parent| *parent != coor) {
+            walls[coor.0][coor.1] = false;
+            coor = parent;
+        }
+    }
+}
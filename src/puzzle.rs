@@ -0,0 +1,340 @@
+//! Puzzle Module
+//!
+//! Single-player puzzles: from a given position, find the moves that win
+//! within a number of moves against a scripted opponent.
+//!
+//! * goals: collect some ATK, or trap the opponent (no legal action left for it)
+//! * the opponent always keeps the most room (`get_scripted_action`), so a
+//!   puzzle is a one-player search the solver can cover completely
+//! * the generator draws positions until the shortest win is unique, then
+//!   grades it by the size of the tree the solver had to search
+//!
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::action::{get_legal_action, get_next_player_to_move, Action};
+use crate::result::result_function::result_after_action;
+use crate::rules::RuleSet;
+use crate::search::evaluation::get_distance_map;
+use crate::state_repr::{GameState, MapRandom};
+use crate::terminal::is_terminal_state;
+
+/// Most searched moves for an easy puzzle
+pub const EASY_MAX_NODES: u32 = 15;
+
+/// Most searched moves for a medium puzzle
+pub const MEDIUM_MAX_NODES: u32 = 60;
+
+/// Kind of goal of a puzzle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GoalKind {
+    CollectAtk,
+    TrapOpponent,
+}
+
+/// What the player must do
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PuzzleGoal {
+    /// Reach this much ATK
+    CollectAtk(i16),
+    /// Leave the opponent without legal action while the player can still move
+    TrapOpponent,
+}
+
+impl PuzzleGoal {
+    /// Sentence for the player
+    pub fn get_description(&self) -> String {
+        match self {
+            PuzzleGoal::CollectAtk(target) => format!("reach {target} ATK"),
+            PuzzleGoal::TrapOpponent => "trap the opponent".to_string(),
+        }
+    }
+}
+
+/// Difficulty of a puzzle, from the searched moves
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// Difficulty of a puzzle whose solver searched `nodes` moves
+    pub fn from_nodes(nodes: u32) -> Difficulty {
+        match nodes {
+            nodes if nodes <= EASY_MAX_NODES => Difficulty::Easy,
+            nodes if nodes <= MEDIUM_MAX_NODES => Difficulty::Medium,
+            _ => Difficulty::Hard,
+        }
+    }
+}
+
+/// A generated puzzle
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    pub state: GameState,
+    /// Player solving the puzzle, to move in `state`
+    pub player: u8,
+    pub goal: PuzzleGoal,
+    /// Moves of the player to reach the goal
+    pub max_moves: u32,
+    /// The only winning moves
+    pub solution: Vec<Action>,
+    /// Moves searched by the solver
+    pub nodes: u32,
+    pub difficulty: Difficulty,
+}
+
+/// After a move of the puzzle
+#[derive(Debug, Clone)]
+pub enum PuzzleStep {
+    /// Goal reached, the state after the move
+    Solved(GameState),
+    /// The game ended or the player is stuck, the last state
+    Failed(GameState),
+    /// State after the answer of the opponent, and its answer
+    Going(GameState, Option<Action>),
+}
+
+/// Winning lines found by the solver
+#[derive(Debug, Clone, Default)]
+pub struct PuzzleSearch {
+    pub solutions: Vec<Vec<Action>>,
+    /// Moves searched
+    pub nodes: u32,
+}
+
+/// Whether the player reached the goal
+pub fn is_goal_reached(state: &GameState, player: u8, goal: &PuzzleGoal) -> bool {
+    match goal {
+        PuzzleGoal::CollectAtk(target) => state.get_player_atk(player) >= *target,
+        PuzzleGoal::TrapOpponent => {
+            !get_legal_action(state, player).is_empty()
+                && state
+                    .get_enemies(player)
+                    .iter()
+                    .all(|enemy| get_legal_action(state, *enemy).is_empty())
+        }
+    }
+}
+
+///
+/// Action of the scripted opponent: the legal action leaving it the most
+/// reachable tiles, the first one on ties
+///
+/// # Returns
+///
+/// None if the player has no legal action
+///
+pub fn get_scripted_action(state: &GameState, player: u8) -> Option<Action> {
+    let mut best: Option<(usize, Action)> = None;
+    for action in get_legal_action(state, player) {
+        let next_state = result_after_action(state, &action, player);
+        let room = get_distance_map(&next_state, next_state.get_player_coor(player))
+            .iter()
+            .flatten()
+            .filter(|distance| distance.is_some())
+            .count();
+        if best.is_none_or(|(best_room, _)| room > best_room) {
+            best = Some((room, action));
+        }
+    }
+    best.map(|(_, action)| action)
+}
+
+///
+/// Play a move of the puzzle and the answers of the opponents
+///
+/// # Parameters
+///
+/// * state: the state, the player to move
+/// * player: Player solving the puzzle
+/// * goal: Goal of the puzzle
+/// * action: a legal action of the player
+///
+pub fn play_puzzle_move(state: &GameState, player: u8, goal: &PuzzleGoal, action: &Action) -> PuzzleStep {
+    let mut state = result_after_action(state, action, player);
+    if is_goal_reached(&state, player, goal) {
+        return PuzzleStep::Solved(state);
+    }
+    let mut answer = None;
+    let mut to_move = get_next_player_to_move(&state, player);
+    while to_move != player && !is_terminal_state(&state) {
+        let Some(action) = get_scripted_action(&state, to_move) else {
+            break;
+        };
+        state = result_after_action(&state, &action, to_move);
+        answer = Some(action);
+        to_move = get_next_player_to_move(&state, to_move);
+    }
+    if is_terminal_state(&state) || get_legal_action(&state, player).is_empty() {
+        return PuzzleStep::Failed(state);
+    }
+    PuzzleStep::Going(state, answer)
+}
+
+///
+/// Search the winning lines of the player
+///
+/// # Parameters
+///
+/// * state: the state, the player to move
+/// * player: Player solving the puzzle
+/// * goal: Goal of the puzzle
+/// * max_moves: Moves of the player
+/// * max_solutions: the search stops after finding this many lines
+///
+/// # Returns
+///
+/// The winning lines (at most `max_moves` long) and the moves searched
+///
+pub fn solve_puzzle(
+    state: &GameState,
+    player: u8,
+    goal: &PuzzleGoal,
+    max_moves: u32,
+    max_solutions: usize,
+) -> PuzzleSearch {
+    let mut search = PuzzleSearch::default();
+    let mut line = Vec::new();
+    search_solutions(state, player, goal, max_moves, max_solutions, &mut line, &mut search);
+    search
+}
+
+/// Depth first search of `solve_puzzle`
+fn search_solutions(
+    state: &GameState,
+    player: u8,
+    goal: &PuzzleGoal,
+    moves_left: u32,
+    max_solutions: usize,
+    line: &mut Vec<Action>,
+    search: &mut PuzzleSearch,
+) {
+    if moves_left == 0 {
+        return;
+    }
+    for action in get_legal_action(state, player) {
+        if search.solutions.len() >= max_solutions {
+            return;
+        }
+        search.nodes += 1;
+        line.push(action);
+        match play_puzzle_move(state, player, goal, &action) {
+            PuzzleStep::Solved(_) => search.solutions.push(line.clone()),
+            PuzzleStep::Going(next_state, _) => {
+                search_solutions(&next_state, player, goal, moves_left - 1, max_solutions, line, search)
+            }
+            PuzzleStep::Failed(_) => {}
+        }
+        line.pop();
+    }
+}
+
+/// Settings of the puzzle generator
+#[derive(Debug, Clone)]
+pub struct PuzzleConfig {
+    /// Rules of the puzzles, always two players
+    pub rules: RuleSet,
+    pub map_randomness: MapRandom,
+    /// Goal of the puzzles, None for any
+    pub goal: Option<GoalKind>,
+    /// ATK to collect on top of the ATK of the position
+    pub atk_gain: i16,
+    /// Fewest moves of the solution
+    pub min_moves: u32,
+    /// Most moves of the solution
+    pub max_moves: u32,
+    /// Wanted difficulty, None for any
+    pub difficulty: Option<Difficulty>,
+    /// Positions drawn before giving up
+    pub attempts: u32,
+    pub seed: u64,
+}
+
+impl Default for PuzzleConfig {
+    fn default() -> Self {
+        PuzzleConfig {
+            rules: RuleSet::with_board_size(6),
+            map_randomness: MapRandom::FullyRandom,
+            goal: None,
+            atk_gain: 2,
+            min_moves: 2,
+            max_moves: 6,
+            difficulty: None,
+            attempts: 500,
+            seed: 0,
+        }
+    }
+}
+
+/// Random position: a map of the rules and a few random moves of both players
+fn draw_puzzle_position(config: &PuzzleConfig, rng: &mut StdRng) -> Option<GameState> {
+    let mut rules = config.rules.clone();
+    rules.board.players = 2;
+    let mut state = GameState::new_with_rules(&rules, config.map_randomness, rng.gen());
+    let mut player = 0;
+    for _ in 0..rng.gen_range(0..=3) * 2 {
+        let action = *get_legal_action(&state, player).choose(rng)?;
+        state = result_after_action(&state, &action, player);
+        if is_terminal_state(&state) {
+            return None;
+        }
+        player = get_next_player_to_move(&state, player);
+    }
+    (player == 0).then_some(state)
+}
+
+///
+/// Generate a puzzle whose shortest win is unique
+///
+/// # Parameters
+///
+/// * config: Generator settings, the same seed gives the same puzzle
+///
+/// # Returns
+///
+/// The puzzle, None if no position of the attempts fits the settings
+///
+pub fn generate_puzzle(config: &PuzzleConfig) -> Option<Puzzle> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    for _ in 0..config.attempts {
+        let Some(state) = draw_puzzle_position(config, &mut rng) else {
+            continue;
+        };
+        let kind = config
+            .goal
+            .unwrap_or(*[GoalKind::CollectAtk, GoalKind::TrapOpponent].choose(&mut rng)?);
+        let goal = match kind {
+            GoalKind::CollectAtk => PuzzleGoal::CollectAtk(state.get_player_atk(0) + config.atk_gain),
+            GoalKind::TrapOpponent => PuzzleGoal::TrapOpponent,
+        };
+
+        // shortest win first, longer limits have more lines
+        let Some((max_moves, search)) = (1..=config.max_moves)
+            .map(|max_moves| (max_moves, solve_puzzle(&state, 0, &goal, max_moves, 2)))
+            .find(|(_, search)| !search.solutions.is_empty())
+        else {
+            continue;
+        };
+        if max_moves < config.min_moves || search.solutions.len() != 1 {
+            continue;
+        }
+        let difficulty = Difficulty::from_nodes(search.nodes);
+        if config.difficulty.is_some_and(|wanted| wanted != difficulty) {
+            continue;
+        }
+        return Some(Puzzle {
+            state,
+            player: 0,
+            goal,
+            max_moves,
+            solution: search.solutions[0].clone(),
+            nodes: search.nodes,
+            difficulty,
+        });
+    }
+    None
+}
@@ -0,0 +1,97 @@
+//! Respawn Module
+//!
+//! Bonus and skill tiles coming back on the map every few turns.
+//! A spawn only depends on the map seed, the turn and the board at that time,
+//! so the next one can be shown to the players before it happens.
+//!
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::rules::GenerationRules;
+use crate::state_repr::{draw_random_tile, GameElements, GameState};
+
+/// Tiles placed by a spawn
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpawnEvent {
+    /// The spawn happens after this turn
+    pub turn: u32,
+    /// Coordinate and tile
+    pub tiles: Vec<((usize, usize), GameElements)>,
+}
+
+/// Turn of the next spawn, None if the respawn is off
+pub fn get_next_spawn_turn(state: &GameState) -> Option<u32> {
+    let respawn = &state.get_rules().respawn;
+    if !respawn.enabled {
+        return None;
+    }
+    let interval = respawn.interval.max(1);
+    Some((state.get_turn_count() / interval + 1) * interval)
+}
+
+/// Next spawn, with the tiles it would place on the current board.
+/// Tiles may still change if the board changes before the spawn.
+pub fn get_next_spawn(state: &GameState) -> Option<SpawnEvent> {
+    let turn = get_next_spawn_turn(state)?;
+    Some(draw_spawn(state, turn))
+}
+
+/// Place the tiles of the spawn due at the current turn, if any
+pub fn respawn_tiles(state: &mut GameState) {
+    let respawn = &state.get_rules().respawn;
+    let turn = state.get_turn_count();
+    if !respawn.enabled || turn == 0 || !turn.is_multiple_of(respawn.interval.max(1)) {
+        return;
+    }
+    for ((x, y), tile) in draw_spawn(state, turn).tiles {
+        state.change_elem_on_coor(x, y, tile);
+    }
+}
+
+///
+/// Draw the tiles of the spawn of a turn on the current board.
+/// The tiles and the order of the candidate coordinates only depend on the
+/// seed and the turn, the board only decides which candidates are skipped:
+/// only empty passable tiles without a player can receive a tile.
+///
+fn draw_spawn(state: &GameState, turn: u32) -> SpawnEvent {
+    let mut rng = StdRng::seed_from_u64(
+        state.get_seed() ^ u64::from(turn).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+    );
+    let weights = get_spawn_weights(&state.get_rules().generation);
+    let drawn: Vec<GameElements> = (0..state.get_rules().respawn.count)
+        .map(|_| draw_random_tile(&weights, &mut rng))
+        .filter(|tile| *tile != GameElements::Passable)
+        .collect();
+
+    let positions = state.get_player_positions();
+    let size = state.get_height_width();
+    let mut candidates: Vec<(usize, usize)> = (0..size)
+        .flat_map(|x| (0..size).map(move |y| (x, y)))
+        .collect();
+    candidates.shuffle(&mut rng);
+
+    let free = candidates.into_iter().filter(|coor| {
+        *state.get_elem_on_coor(coor.0, coor.1) == GameElements::Passable
+            && !positions.contains(coor)
+    });
+    SpawnEvent {
+        turn,
+        tiles: free.zip(drawn).collect(),
+    }
+}
+
+/// Generation weights of the bonus and skill tiles only
+fn get_spawn_weights(generation: &GenerationRules) -> GenerationRules {
+    GenerationRules {
+        passable: 0,
+        non_passable: 0,
+        ice: 0,
+        conveyor: 0,
+        skip_trap: 0,
+        atk_trap: 0,
+        teleporter_pairs: 0,
+        ..generation.clone()
+    }
+}
@@ -1,6 +1,8 @@
-use crate::action::Action;
-use crate::state_repr::{GameElements, GameState, SpecialSkill};
-use crate::utils::is_not_out_of_bound;
+use crate::action::{Action, Direction, SkillTarget};
+use crate::arena::shrink_arena;
+use crate::respawn::respawn_tiles;
+use crate::state_repr::{is_passable_elem, GameElements, GameState};
+use crate::skill::{get_dash_targets, get_offset_coor, get_sp_skill_of_tile, SpecialSkill};
 
 
 /// Get the state result from an action to a state
@@ -9,119 +11,563 @@ use crate::utils::is_not_out_of_bound;
 /// 
 /// * state: Game state
 /// * action: Action done by the player
-/// * player: id player, 0 or 1
+/// * player: id player
 /// 
 /// # Return
 /// GameState: Return a new object game state
 /// 
 pub fn result_after_action(state: &GameState, action: &Action, player: u8) -> GameState {
-    let (xp, yp) = state.get_player_position_with_idx(player);
-
     // We will create a new state cloned from the original one
     let mut new_state = state.clone();
-
-    match action {
-        Action::Down => {
-            // Go down
-            // curent position become Non passable
-            new_state.change_elem_on_coor(xp as usize, yp as usize, GameElements::NonPassable);
-            new_state.change_player_pos(player, xp as usize, (yp + 1) as usize);
-            change_game_state_after_move(&mut new_state, player);
-        }
-        Action::Up => {
-            // Go down
-            new_state.change_elem_on_coor(xp as usize, yp as usize, GameElements::NonPassable);
-            new_state.change_player_pos(player, xp as usize, (yp - 1) as usize);
-            change_game_state_after_move(&mut new_state, player);
-        }
-        Action::Left => {
-            // Go Left
-            new_state.change_elem_on_coor(xp as usize, yp as usize, GameElements::NonPassable);
-            new_state.change_player_pos(player, (xp - 1) as usize, yp as usize);
-            change_game_state_after_move(&mut new_state, player);
-        }
-        Action::Right => {
-            // Go Right TODO refactor repetition
-            new_state.change_elem_on_coor(xp as usize, yp as usize, GameElements::NonPassable);
-            new_state.change_player_pos(player, (xp + 1) as usize, yp as usize);
-            change_game_state_after_move(&mut new_state, player);
-        }
-        Action::SpSkill => {
-            // use special skill! Does not move
-            use_sp_skill(&mut new_state, player);
-        }
-    }
+    begin_action(&mut new_state, player);
+    apply_action(&mut new_state, action, player);
+    end_action(&mut new_state, player);
     new_state
 }
 
-/// Get unpassable when doing clear road special skill
+/// What moves a player in a round (simultaneous mode)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MoveKind {
+    /// Its own move, teleport or dash
+    Own,
+    /// Knocked back by an attack
+    Knockback,
+    /// Swapped with an enemy, the tiles under them are left as they are
+    Swapped,
+}
+
+/// A player changing tile in a round (simultaneous mode)
+#[derive(Debug, Clone, Copy)]
+struct RoundMove {
+    player: u8,
+    kind: MoveKind,
+    /// Tile at the start of the round
+    from: (usize, usize),
+    to: (usize, usize),
+    /// Tile a dash runs through
+    passed: Option<(usize, usize)>,
+    /// Direction of the move, None for a jump
+    direction: Option<Direction>,
+}
+
+/// Get the state result from the actions of a round (simultaneous mode)
+///
+/// Every action is resolved on the state at the start of the round,
+/// nobody has priority:
+///
+/// * attacks and swaps aim at the enemy on its tile at the start of the round,
+///   an enemy moving away dodges them. Two swaps on a player cancel each other,
+///   a player hit by several attacks takes the damage without knockback
+/// * a player moving into a tile left this round (or run through by a dash)
+///   hits the trail and bounces back: its own tile becomes non passable,
+///   knocked back it takes the wall damage instead
+/// * enemies landing on the same tile collide: they stay on it without its effect
+///   (a bonus goes to nobody), the game ends if `collision_ends_game`
+/// * walls are not placed under a player
+///
+/// Then the tiles landed on take effect in player order (a slide stops at a wall
+/// or an enemy), a dash only takes the effect of its landing tile.
+/// The arena, the respawns and the turn count move on once per round.
 ///
+/// # Parameters
+///
+/// * state: Game state at the start of the round
+/// * actions: Action of every player that can play, as (player, action)
+///
+/// # Return
+/// GameState: Return a new object game state
 ///
-fn get_unpassable_on_clear_road_sp_skill(state: &GameState, xp: i8, yp: i8) -> Vec<(i8, i8)> {
-    // check Up (check if < 0)
-    let tuple_check = [(xp, yp - 1), (xp, yp + 1), (xp + 1, yp), (xp - 1, yp)];
-    let mut tuple_filtered = Vec::new();
-    for coor in tuple_check {
-        if is_not_out_of_bound(coor.0, coor.1, state.get_height_width() as i8) {
-            match state.get_elem_on_coor(coor.0 as usize, coor.1 as usize) {
-                GameElements::NonPassable => {
-                    tuple_filtered.push(coor);
+pub fn result_after_round(state: &GameState, actions: &[(u8, Action)]) -> GameState {
+    let mut new_state = state.clone();
+    for (player, _) in actions.iter() {
+        begin_action(&mut new_state, *player);
+    }
+
+    let mut moves: Vec<RoundMove> = actions
+        .iter()
+        .filter_map(|(player, action)| get_own_move(state, *player, action))
+        .collect();
+    let own_movers: Vec<u8> = moves.iter().map(|round_move| round_move.player).collect();
+    let target_of = |player: u8, coor: Option<(usize, usize)>| {
+        coor.and_then(|coor| state.get_enemy_on_coor(player, coor))
+            .filter(|enemy| !own_movers.contains(enemy))
+    };
+
+    // (attacker, enemy, direction) and (player, enemy)
+    let mut hits: Vec<(u8, u8, Direction)> = Vec::new();
+    let mut swaps: Vec<(u8, u8)> = Vec::new();
+    for (player, action) in actions.iter() {
+        let own = state.get_player_coor(*player);
+        match action {
+            Action::Attack(direction) => {
+                if let Some(enemy) = target_of(*player, get_offset_coor(state, own, *direction)) {
+                    hits.push((*player, enemy, *direction));
+                }
+            }
+            Action::SpSkill(slot, target)
+                if *state.get_player_sp(*player, *slot) == SpecialSkill::Swap =>
+            {
+                let enemies = state.get_enemies(*player);
+                let enemy = match target {
+                    SkillTarget::Coordinate(x, y) => target_of(*player, Some((*x, *y))),
+                    _ if enemies.len() == 1 => {
+                        target_of(*player, Some(state.get_player_coor(enemies[0])))
+                    }
+                    _ => None,
+                };
+                if let Some(enemy) = enemy {
+                    swaps.push((*player, enemy));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let swap_count = |player: u8| {
+        swaps
+            .iter()
+            .filter(|swap| swap.0 == player || swap.1 == player)
+            .count()
+    };
+    for (player, enemy) in swaps.iter().copied() {
+        if swap_count(player) > 1 || swap_count(enemy) > 1 {
+            continue;
+        }
+        let (own, enemy_coor) = (state.get_player_coor(player), state.get_player_coor(enemy));
+        for (swapped, from, to) in [(player, own, enemy_coor), (enemy, enemy_coor, own)] {
+            moves.push(RoundMove {
+                player: swapped,
+                kind: MoveKind::Swapped,
+                from,
+                to,
+                passed: None,
+                direction: None,
+            });
+        }
+    }
+
+    let combat = state.get_rules().combat.clone();
+    for (attacker, enemy, direction) in hits.iter().copied() {
+        let damage = (combat.base_damage + state.get_player_atk(attacker)).max(1);
+        new_state.change_player_hp(enemy, -damage);
+        let hit_once = hits.iter().filter(|hit| hit.1 == enemy).count() == 1;
+        if !hit_once || moves.iter().any(|round_move| round_move.player == enemy) {
+            continue;
+        }
+        let from = state.get_player_coor(enemy);
+        match get_offset_coor(state, from, direction) {
+            Some(to) if !is_blocked(state, enemy, to) => moves.push(RoundMove {
+                player: enemy,
+                kind: MoveKind::Knockback,
+                from,
+                to,
+                passed: None,
+                direction: Some(direction),
+            }),
+            _ => new_state.change_player_hp(enemy, -combat.wall_damage),
+        }
+    }
+
+    // trails hit this round, all at once: a bounce can make other trails disappear
+    let mut bounced = vec![false; moves.len()];
+    loop {
+        let trails: Vec<(usize, usize)> = moves
+            .iter()
+            .zip(bounced.iter())
+            .filter(|(round_move, bounce)| !**bounce && round_move.kind != MoveKind::Swapped)
+            .flat_map(|(round_move, _)| std::iter::once(round_move.from).chain(round_move.passed))
+            .collect();
+        let hitting: Vec<usize> = (0..moves.len())
+            .filter(|idx| {
+                !bounced[*idx]
+                    && moves[*idx].kind != MoveKind::Swapped
+                    && trails.contains(&moves[*idx].to)
+            })
+            .collect();
+        if hitting.is_empty() {
+            break;
+        }
+        for idx in hitting {
+            bounced[idx] = true;
+        }
+    }
+
+    let final_coor = |player: u8| {
+        moves
+            .iter()
+            .zip(bounced.iter())
+            .find(|(round_move, bounce)| round_move.player == player && !**bounce)
+            .map_or(state.get_player_coor(player), |(round_move, _)| {
+                round_move.to
+            })
+    };
+    let collided = |round_move: &RoundMove| {
+        state
+            .get_enemies(round_move.player)
+            .into_iter()
+            .any(|enemy| final_coor(enemy) == round_move.to)
+    };
+
+    for (round_move, bounce) in moves.iter().zip(bounced.iter()) {
+        match (round_move.kind, bounce) {
+            (MoveKind::Swapped, _) => (),
+            (MoveKind::Knockback, true) => {
+                new_state.change_player_hp(round_move.player, -combat.wall_damage)
+            }
+            (_, true) => leave_tile(&mut new_state, round_move.player),
+            (_, false) => {
+                for (x, y) in std::iter::once(round_move.from).chain(round_move.passed) {
+                    new_state.change_elem_on_coor(x, y, GameElements::NonPassable);
+                }
+            }
+        }
+    }
+    for (round_move, _) in moves
+        .iter()
+        .zip(bounced.iter())
+        .filter(|(_, bounce)| !**bounce)
+    {
+        new_state.change_player_pos(round_move.player, round_move.to.0, round_move.to.1);
+    }
+
+    for (player, action) in actions.iter() {
+        let Action::SpSkill(slot, target) = action else {
+            continue;
+        };
+        let own = state.get_player_coor(*player);
+        match (state.get_player_sp(*player, *slot), target) {
+            (SpecialSkill::ClearRoadTile, SkillTarget::Direction(direction)) => {
+                if let Some((x, y)) = get_offset_coor(state, own, *direction) {
+                    new_state.change_elem_on_coor(x, y, GameElements::Passable);
                 }
-                _ => (),
+            }
+            (SpecialSkill::ReduceEnemiesATK, _) => {
+                let amount = state.get_rules().skills.reduce_enemy_atk_amount;
+                for enemy in state.get_enemies(*player) {
+                    new_state.change_player_atk(enemy, -amount);
+                }
+            }
+            (SpecialSkill::PlaceWall, SkillTarget::Coordinate(x, y)) => {
+                let players = 0..state.get_player_count() as u8;
+                if !players.clone().any(|other| final_coor(other) == (*x, *y)) {
+                    new_state.change_elem_on_coor(*x, *y, GameElements::NonPassable);
+                }
+            }
+            _ => (),
+        }
+        new_state.remove_player_sp(*player, *slot);
+    }
+
+    let mut landed: Vec<&RoundMove> = moves
+        .iter()
+        .zip(bounced.iter())
+        .filter(|(round_move, bounce)| {
+            !**bounce && round_move.kind != MoveKind::Swapped && !collided(round_move)
+        })
+        .map(|(round_move, _)| round_move)
+        .collect();
+    landed.sort_by_key(|round_move| round_move.player);
+    for round_move in landed {
+        change_game_state_after_move(&mut new_state, round_move.player, round_move.direction);
+    }
+
+    new_state.increment_turn_count();
+    shrink_arena(&mut new_state);
+    respawn_tiles(&mut new_state);
+    new_state
+}
+
+/// Move of the player by its own action in a round, None if it doesn't move
+fn get_own_move(state: &GameState, player: u8, action: &Action) -> Option<RoundMove> {
+    let from = state.get_player_coor(player);
+    let (to, passed, direction) = match action {
+        Action::SpSkill(slot, target) => match (state.get_player_sp(player, *slot), target) {
+            (SpecialSkill::Teleport, SkillTarget::Coordinate(x, y)) => ((*x, *y), None, None),
+            (SpecialSkill::Dash, SkillTarget::Direction(direction)) => {
+                let dash = get_dash_targets(state, player)
+                    .into_iter()
+                    .find(|dash| dash.direction == *direction)?;
+                (dash.landing, Some(dash.passed), Some(*direction))
+            }
+            _ => return None,
+        },
+        _ => {
+            let direction = action.get_move_direction()?;
+            (
+                get_offset_coor(state, from, direction)?,
+                None,
+                Some(direction),
+            )
+        }
+    };
+    Some(RoundMove {
+        player,
+        kind: MoveKind::Own,
+        from,
+        to,
+        passed,
+        direction,
+    })
+}
+
+/// Start of the action of the player:
+/// acting ends the own wait, and the others waited one action
+fn begin_action(state: &mut GameState, player: u8) {
+    state.set_player_wait(player, 0);
+    for other in (0..state.get_player_count() as u8).filter(|other| *other != player) {
+        state.set_player_wait(other, state.get_player_wait(other).saturating_sub(1));
+    }
+}
+
+/// Play the action of the player on the state
+fn apply_action(new_state: &mut GameState, action: &Action, player: u8) {
+    match action {
+        Action::SpSkill(slot, target) => {
+            // use special skill! Only teleport and dash move
+            use_sp_skill(new_state, player, *slot, target);
+        }
+        Action::Attack(direction) => attack(new_state, player, direction),
+        // curent position become Non passable
+        _ => {
+            let own = new_state.get_player_coor(player);
+            let target = action
+                .get_move_direction()
+                .and_then(|direction| get_offset_coor(new_state, own, direction));
+            if let Some((x, y)) = target {
+                move_player_to(new_state, player, x, y);
             }
         }
     }
-    return tuple_filtered;
+}
+
+/// End of the action of the player: the turn is counted,
+/// then the arena and the respawns take effect
+fn end_action(state: &mut GameState, player: u8) {
+    // trapped by its own action: the next round of enemy actions doesn't count
+    if state.get_player_wait(player) > 0 {
+        let enemies = state.get_enemies(player).len().max(1) as u8;
+        state.set_player_wait(player, state.get_player_wait(player).saturating_add(enemies));
+    }
+
+    state.increment_turn_count();
+    shrink_arena(state);
+    respawn_tiles(state);
 }
 
 /// Result function on using a special skill
-/// Update the state directly
+/// Update the state directly.
+/// The target must come from `get_legal_action`, a target that doesn't
+/// fit the skill only consumes it.
 /// 
 /// # Parameters
 /// 
 /// * state: Mutable Game State to change it.
 /// * player: Player ID
+/// * slot: Inventory slot of the skill
+/// * target: Where the skill is aimed
 /// 
-fn use_sp_skill(state: &mut GameState, player: u8) {
+fn use_sp_skill(state: &mut GameState, player: u8, slot: usize, target: &SkillTarget) {
     let (xp, yp) = state.get_player_position_with_idx(player);
-    let sp_skill = state.get_player_sp(player);
-    let opposite_player = 1 - player;
+    let sp_skill = state.get_player_sp(player, slot).clone();
+    // removed first, so a skill picked up by the move itself is kept
+    state.remove_player_sp(player, slot);
 
-    match sp_skill {
-        SpecialSkill::ClearRoadTile => {
-            // Clear NonPassable and becomes passable
-            // check up, down, left, right
-            let coor_not_passable = get_unpassable_on_clear_road_sp_skill(&state, xp as i8, yp as i8);
-            for coor in coor_not_passable { 
-                // change the state to passable
-                state.change_elem_on_coor(coor.0 as usize, coor.1 as usize, GameElements::Passable);
+    match (sp_skill, target) {
+        (SpecialSkill::ClearRoadTile, SkillTarget::Direction(direction)) => {
+            // Clear NonPassable in the direction and becomes passable
+            let coor = get_offset_coor(state, (xp as usize, yp as usize), *direction);
+            if let Some((x, y)) = coor {
+                state.change_elem_on_coor(x, y, GameElements::Passable);
+            }
+        }
+        (SpecialSkill::ReduceEnemiesATK, _) => {
+            let amount = state.get_rules().skills.reduce_enemy_atk_amount;
+            for enemy in state.get_enemies(player) {
+                state.change_player_atk(enemy, -amount);
+            }
+        }
+        (SpecialSkill::Teleport, SkillTarget::Coordinate(x, y)) => {
+            move_player_to(state, player, *x, *y);
+        }
+        (SpecialSkill::PlaceWall, SkillTarget::Coordinate(x, y)) => {
+            state.change_elem_on_coor(*x, *y, GameElements::NonPassable);
+        }
+        (SpecialSkill::Swap, target) => {
+            // tiles under the players are left as they are
+            let enemies = state.get_enemies(player);
+            let enemy = match target {
+                SkillTarget::Coordinate(x, y) => state.get_enemy_on_coor(player, (*x, *y)),
+                _ if enemies.len() == 1 => Some(enemies[0]),
+                _ => None,
+            };
+            if let Some(enemy) = enemy {
+                let (xe, ye) = state.get_player_coor(enemy);
+                state.change_player_pos(enemy, xp as usize, yp as usize);
+                state.change_player_pos(player, xe, ye);
             }
         }
-        SpecialSkill::ReduceEnemiesATK => {
-            state.change_player_atk(opposite_player, -1);
+        (SpecialSkill::Dash, SkillTarget::Direction(direction)) => {
+            // run through the first tile, then land on the second one
+            let dash = get_dash_targets(state, player)
+                .into_iter()
+                .find(|dash| dash.direction == *direction);
+            if let Some(dash) = dash {
+                move_player_to(state, player, dash.passed.0, dash.passed.1);
+                // the tile run through may have moved the player already
+                let (x, y) = state.get_player_position_with_idx(player);
+                if (x as usize, y as usize) == dash.passed {
+                    move_player_to(state, player, dash.landing.0, dash.landing.1);
+                }
+            }
         }
         _ => (),
     };
-    state.set_player_sp(player, SpecialSkill::None);
+}
+
+///
+/// Result function of an attack in combat mode. Update the state directly.
+/// The enemy on the next tile is knocked back one tile in the direction of
+/// the attack, or takes the wall damage if the tile behind is blocked.
+///
+fn attack(state: &mut GameState, player: u8, direction: &Direction) {
+    let combat = state.get_rules().combat.clone();
+    let own = state.get_player_coor(player);
+    let enemy = get_offset_coor(state, own, *direction)
+        .and_then(|coor| state.get_enemy_on_coor(player, coor));
+    let Some(enemy) = enemy else {
+        return;
+    };
+    let damage = (combat.base_damage + state.get_player_atk(player)).max(1);
+    state.change_player_hp(enemy, -damage);
+
+    let behind = get_offset_coor(state, state.get_player_coor(enemy), *direction);
+    match behind {
+        Some((x, y)) if !is_blocked(state, enemy, (x, y)) => {
+            move_player_to(state, enemy, x, y);
+        }
+        _ => state.change_player_hp(enemy, -combat.wall_damage),
+    }
+}
+
+///
+/// Move the player to a new tile: the current tile becomes non passable
+/// and the new tile takes effect.
+///
+fn move_player_to(state: &mut GameState, player: u8, x: usize, y: usize) {
+    let own = state.get_player_coor(player);
+    let direction = state.get_topology().get_direction_between(own, (x, y));
+    leave_tile(state, player);
+    state.change_player_pos(player, x, y);
+    change_game_state_after_move(state, player, direction);
+}
+
+///
+/// The tile the player leaves becomes non passable,
+/// a teleporter left unused takes its pair with it (see `change_elem_on_coor`).
+///
+fn leave_tile(state: &mut GameState, player: u8) {
+    let (xp, yp) = state.get_player_coor(player);
+    state.change_elem_on_coor(xp, yp, GameElements::NonPassable);
 }
 
 ///
 /// Change Game state (like stepping on bonuses) after move.
+/// Ice, conveyors and teleporters move the player again.
 ///
-fn change_game_state_after_move(state: &mut GameState, player: u8) {
+/// # Parameters
+///
+/// * state: Mutable Game State to change it.
+/// * player: Player ID
+/// * direction: Direction of the move, None if the player jumped
+///
+fn change_game_state_after_move(state: &mut GameState, player: u8, direction: Option<Direction>) {
     let (xp, yp) = state.get_player_position_with_idx(player);
-    let game_element_on_coor = state.get_map_representation()[xp as usize][yp as usize];
+    let coor = (xp as usize, yp as usize);
+    let game_element_on_coor = state.get_map_representation()[coor.0][coor.1];
 
     match game_element_on_coor {
         GameElements::BonusATKBonusTile => {
-            state.change_player_atk(player, 1);
+            let gain = state.get_rules().tiles.atk_bonus_gain;
+            state.change_player_atk(player, gain);
         }
-        GameElements::MinusATKBonusSpTile => {
-            state.set_player_sp(player, SpecialSkill::ReduceEnemiesATK);
+        GameElements::IceTile => {
+            if let Some(direction) = direction {
+                slide_player(state, player, direction);
+            }
         }
-        GameElements::ClearRoadBonusSpTile => {
-            state.set_player_sp(player, SpecialSkill::ClearRoadTile);
+        GameElements::ConveyorUpTile => {
+            push_player(state, player, Direction::Up);
         }
-        _ => (),
+        GameElements::ConveyorDownTile => {
+            push_player(state, player, Direction::Down);
+        }
+        GameElements::ConveyorRightTile => {
+            push_player(state, player, Direction::Right);
+        }
+        GameElements::ConveyorLeftTile => {
+            push_player(state, player, Direction::Left);
+        }
+        GameElements::TeleporterTile => {
+            // the pair is used up: covering this teleporter turns the landing one passable
+            if let Some((x, y)) = state.get_paired_teleporter(coor) {
+                if state.get_enemy_on_coor(player, (x, y)).is_none() {
+                    state.change_elem_on_coor(coor.0, coor.1, GameElements::NonPassable);
+                    state.change_player_pos(player, x, y);
+                }
+            }
+        }
+        GameElements::SkipTrapTile => {
+            // every enemy action counts down the wait once
+            let enemies = state.get_enemies(player).len().max(1) as u8;
+            let skip = state.get_rules().tiles.trap_skip_turns;
+            state.set_player_wait(player, skip.saturating_mul(enemies));
+        }
+        GameElements::ATKTrapTile => {
+            let loss = state.get_rules().tiles.trap_atk_loss;
+            state.change_player_atk(player, -loss);
+        }
+        elem => {
+            if let Some(sp_skill) = get_sp_skill_of_tile(&elem) {
+                state.add_player_sp(player, sp_skill);
+            }
+        }
+    }
+}
+
+///
+/// Move the player one tile in the direction,
+/// nothing happens if the tile is blocked by a wall, the border or an enemy,
+/// or if the grid has no such direction (up and down conveyors on a hex grid).
+///
+/// # Returns
+/// The tile the player was pushed to, None if blocked
+///
+fn push_player(state: &mut GameState, player: u8, direction: Direction) -> Option<(usize, usize)> {
+    let (xp, yp) = state.get_player_position_with_idx(player);
+    let (x, y) = get_offset_coor(state, (xp as usize, yp as usize), direction)?;
+    if is_blocked(state, player, (x, y)) {
+        return None;
     }
+    move_player_to(state, player, x, y);
+    Some((x, y))
+}
+
+///
+/// Slide from an ice tile until blocked. Every tile passed takes effect,
+/// the slide stops when a tile moves the player somewhere else.
+///
+fn slide_player(state: &mut GameState, player: u8, direction: Direction) {
+    while let Some(pushed_to) = push_player(state, player, direction) {
+        let (x, y) = state.get_player_position_with_idx(player);
+        if (x as usize, y as usize) != pushed_to {
+            break;
+        }
+    }
+}
+
+/// Whether a wall or an enemy of the player stands on the tile,
+/// team-mates pass through each other
+fn is_blocked(state: &GameState, player: u8, coor: (usize, usize)) -> bool {
+    !is_passable_elem(state.get_elem_on_coor(coor.0, coor.1))
+        || state.get_enemy_on_coor(player, coor).is_some()
 }
@@ -0,0 +1,407 @@
+//! Rules Module
+//!
+//! Every tunable rule of the game, loadable from a TOML or JSON file
+//! so variants can be played without recompiling.
+//! Missing values keep their default, so a file only lists what it changes:
+//!
+//! ```toml
+//! [board]
+//! size = 7
+//!
+//! [tiles]
+//! atk_bonus_gain = 2
+//!
+//! [scoring]
+//! win = 3
+//! lose = 0
+//! draw = 1
+//! ```
+//!
+//! The rule set is shared by a `GameState` and all the states derived from it.
+//!
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::state_repr::MAX_PLAYERS;
+
+/// Smallest board, two players need two tiles
+pub const MIN_BOARD_SIZE: usize = 2;
+
+/// Largest board, coordinates are stored on an i8
+pub const MAX_BOARD_SIZE: usize = i8::MAX as usize;
+
+/// Grid of the board: which tiles are next to each other
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GridKind {
+    /// 4 directions: up, down, left, right
+    Square,
+    /// 8 directions, the diagonals too
+    Octile,
+    /// 6 directions, every row is shifted half a tile to the right of the one above
+    Hex,
+}
+
+/// Board settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BoardRules {
+    /// Height and width of the square board, `MIN_BOARD_SIZE` to `MAX_BOARD_SIZE`
+    pub size: usize,
+    /// Number of players, 2 to 4
+    pub players: usize,
+    /// Players 1 and 3 play against players 2 and 4 (even number of players).
+    /// Team-mates share their score and can pass through each other
+    pub teams: bool,
+    /// Neighbourhood of the tiles
+    pub grid: GridKind,
+    /// The edges wrap around (torus)
+    pub wrap: bool,
+}
+
+impl Default for BoardRules {
+    fn default() -> Self {
+        BoardRules {
+            size: 5,
+            players: 2,
+            teams: false,
+            grid: GridKind::Square,
+            wrap: false,
+        }
+    }
+}
+
+/// Effects of stepping on tiles
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TileRules {
+    /// ATK won on a `BonusATKBonusTile`
+    pub atk_bonus_gain: i16,
+    /// ATK lost on an `ATKTrapTile`
+    pub trap_atk_loss: i16,
+    /// Turns skipped on a `SkipTrapTile`
+    pub trap_skip_turns: u8,
+}
+
+impl Default for TileRules {
+    fn default() -> Self {
+        TileRules {
+            atk_bonus_gain: 1,
+            trap_atk_loss: 1,
+            trap_skip_turns: 1,
+        }
+    }
+}
+
+/// What happens when a player with a full inventory steps on a skill tile
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PickupPolicy {
+    /// The skill in the last slot is overwritten
+    Replace,
+    /// The oldest skill is dropped, the new one goes to the last slot
+    Queue,
+    /// The new skill is not picked up
+    Refuse,
+}
+
+/// Effects of the special skills
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkillRules {
+    /// ATK removed from the enemy by `ReduceEnemiesATK`
+    pub reduce_enemy_atk_amount: i16,
+    /// Max distance (steps on an empty board) of a `Teleport`
+    pub teleport_range: usize,
+    /// Number of skills a player can hold
+    pub inventory_size: usize,
+    /// Pickup on a full inventory
+    pub pickup: PickupPolicy,
+}
+
+impl Default for SkillRules {
+    fn default() -> Self {
+        SkillRules {
+            reduce_enemy_atk_amount: 1,
+            teleport_range: 3,
+            inventory_size: 1,
+            pickup: PickupPolicy::Replace,
+        }
+    }
+}
+
+/// Optional combat mode: players can attack an adjacent enemy.
+/// The attacked player is knocked back one tile, or takes more damage
+/// when knocked into a wall. A player without HP loses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CombatRules {
+    pub enabled: bool,
+    /// HP of the players at the start
+    pub hp: i16,
+    /// Damage of an attack, the ATK of the attacker is added (at least 1 in total)
+    pub base_damage: i16,
+    /// Extra damage when knocked back into a wall or the border
+    pub wall_damage: i16,
+}
+
+impl Default for CombatRules {
+    fn default() -> Self {
+        CombatRules {
+            enabled: false,
+            hp: 5,
+            base_damage: 1,
+            wall_damage: 1,
+        }
+    }
+}
+
+/// Points given at the end of the game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringRules {
+    pub win: i16,
+    pub lose: i16,
+    pub draw: i16,
+    /// Points by final place with more than 2 players, best place first.
+    /// Empty: win / lose / draw are used
+    pub ranking: Vec<i16>,
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        ScoringRules {
+            win: 100,
+            lose: -100,
+            draw: 0,
+            ranking: Vec::new(),
+        }
+    }
+}
+
+/// When the game ends. It always ends when every player is stuck.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EndConditionRules {
+    /// Players landing on the same tile end the game
+    pub collision_ends_game: bool,
+    /// The game ends after this many turns, ATK decides
+    pub max_turns: Option<u32>,
+    /// The first player reaching this ATK wins
+    pub target_atk: Option<i16>,
+    /// The game ends as soon as one player is stuck, the other one wins (light-cycle style)
+    pub last_mover_wins: bool,
+    /// The game ends when the players can't reach each other,
+    /// the player with more reachable tiles wins
+    pub territory_majority: bool,
+}
+
+impl Default for EndConditionRules {
+    fn default() -> Self {
+        EndConditionRules {
+            collision_ends_game: true,
+            max_turns: None,
+            target_atk: None,
+            last_mover_wins: false,
+            territory_majority: false,
+        }
+    }
+}
+
+/// How the players take their turns
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TurnRules {
+    /// Every player commits an action without seeing the others,
+    /// then the actions of the round are resolved together.
+    /// Enemies moving to the same tile collide without getting the tile effect,
+    /// enemies swapping tiles bounce back and leave their trail
+    pub simultaneous: bool,
+}
+
+/// Optional fog of war: a player only sees the tiles within `vision_radius`
+/// (Manhattan distance) of itself and its team-mates, and remembers the tiles
+/// it saw before. Enemies out of sight and their special skills are hidden.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FogRules {
+    pub enabled: bool,
+    pub vision_radius: usize,
+}
+
+impl Default for FogRules {
+    fn default() -> Self {
+        FogRules {
+            enabled: false,
+            vision_radius: 2,
+        }
+    }
+}
+
+/// Optional sudden death: from `start_turn`, the outer ring of the board
+/// becomes `NonPassable` every `interval` turns. A player standing in
+/// a collapsing ring loses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShrinkRules {
+    pub enabled: bool,
+    /// Turn of the first collapse, turns are actions played by both players
+    pub start_turn: u32,
+    /// Turns between two collapses
+    pub interval: u32,
+}
+
+impl Default for ShrinkRules {
+    fn default() -> Self {
+        ShrinkRules {
+            enabled: false,
+            start_turn: 10,
+            interval: 4,
+        }
+    }
+}
+
+/// Optional respawn of bonus and skill tiles: every `interval` turns,
+/// `count` tiles are drawn with the generation weights of the bonus and
+/// skill tiles and placed on random passable tiles.
+/// The draws come from the map seed, so the schedule is known in advance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RespawnRules {
+    pub enabled: bool,
+    pub interval: u32,
+    pub count: usize,
+}
+
+impl Default for RespawnRules {
+    fn default() -> Self {
+        RespawnRules {
+            enabled: false,
+            interval: 5,
+            count: 1,
+        }
+    }
+}
+
+/// Relative weights of the tiles drawn by the random map generator.
+/// Teleport, wall, swap and dash skill tiles, ice, conveyors and traps are off by default.
+/// Teleporters are not drawn but placed in pairs afterwards.
+/// The last settings shape the caves, mazes and rooms generators.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationRules {
+    pub passable: u32,
+    pub non_passable: u32,
+    pub bonus_atk: u32,
+    pub clear_road: u32,
+    pub minus_atk: u32,
+    pub teleport: u32,
+    pub wall: u32,
+    pub swap: u32,
+    pub dash: u32,
+    pub ice: u32,
+    /// Conveyors of any direction
+    pub conveyor: u32,
+    pub skip_trap: u32,
+    pub atk_trap: u32,
+    /// Number of teleporter pairs
+    pub teleporter_pairs: u32,
+    /// Walls of the first draw of the caves, in percent
+    pub cave_fill: u32,
+    /// Smoothing steps of the caves
+    pub cave_steps: u32,
+    /// Chance to knock down an inner wall of the maze, in percent
+    pub maze_loops: u32,
+    pub room_count: u32,
+    /// Side of the rooms, in tiles
+    pub room_min_size: usize,
+    pub room_max_size: usize,
+}
+
+impl Default for GenerationRules {
+    fn default() -> Self {
+        GenerationRules {
+            passable: 5,
+            non_passable: 2,
+            bonus_atk: 1,
+            clear_road: 1,
+            minus_atk: 1,
+            teleport: 0,
+            wall: 0,
+            swap: 0,
+            dash: 0,
+            ice: 0,
+            conveyor: 0,
+            skip_trap: 0,
+            atk_trap: 0,
+            teleporter_pairs: 0,
+            cave_fill: 45,
+            cave_steps: 3,
+            maze_loops: 15,
+            room_count: 4,
+            room_min_size: 2,
+            room_max_size: 4,
+        }
+    }
+}
+
+/// All the rules of a game
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleSet {
+    pub board: BoardRules,
+    pub tiles: TileRules,
+    pub skills: SkillRules,
+    pub combat: CombatRules,
+    pub scoring: ScoringRules,
+    pub end: EndConditionRules,
+    pub turns: TurnRules,
+    pub fog: FogRules,
+    pub shrink: ShrinkRules,
+    pub respawn: RespawnRules,
+    pub generation: GenerationRules,
+}
+
+impl RuleSet {
+    /// Default rules on a board of the given size
+    pub fn with_board_size(size: usize) -> RuleSet {
+        let mut rules = RuleSet::default();
+        rules.board.size = size;
+        rules
+    }
+
+    /// Read rules from a file, JSON if the extension is `.json`, TOML otherwise
+    pub fn load(path: &Path) -> io::Result<RuleSet> {
+        let content = fs::read_to_string(path)?;
+        let is_json = path.extension().is_some_and(|ext| ext == "json");
+        let rules = if is_json {
+            serde_json::from_str(&content).map_err(|err| err.to_string())
+        } else {
+            toml::from_str(&content).map_err(|err| err.to_string())
+        };
+        let rules: RuleSet = rules.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        if !(MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&rules.board.size) {
+            let message = format!("board.size must be {} to {}", MIN_BOARD_SIZE, MAX_BOARD_SIZE);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        if !(2..=MAX_PLAYERS).contains(&rules.board.players) {
+            let message = format!("board.players must be 2 to {}", MAX_PLAYERS);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        if rules.board.teams && !rules.board.players.is_multiple_of(2) {
+            let message = "board.teams needs an even number of players".to_string();
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        Ok(rules)
+    }
+
+    /// Write rules to a TOML file
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let content = toml::to_string(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        fs::write(path, content)
+    }
+}
@@ -0,0 +1,235 @@
+//! Evaluation Module
+//!
+//! Handcrafted evaluation of a non-terminal state.
+//! Scores are always seen from the point of view of one player:
+//! positive is good for that player, negative is good for the opponent.
+//! With more than 2 players, the team of the player is compared against the best
+//! opposing team (every player is its own team outside of team mode).
+//!
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::state_repr::{is_passable_elem, GameElements, GameState};
+use crate::terminal::{get_result_for_player, is_terminal_state};
+
+/// Score of a won terminal state. Any heuristic score stays far below it.
+pub const WIN_SCORE: i32 = 1000;
+
+/// Weights of the evaluation terms, loadable from a TOML file
+///
+/// ```toml
+/// territory = 2.0
+/// atk_difference = 20.0
+/// bonus_proximity = 1.0
+/// held_skill = 5.0
+/// hp_difference = 30.0
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EvalWeights {
+    /// Weight of one tile of territory (tiles reached strictly first)
+    pub territory: f32,
+    /// Weight of one ATK point of difference
+    pub atk_difference: f32,
+    /// Weight of one step closer to the nearest ATK bonus tile
+    pub bonus_proximity: f32,
+    /// Weight of each held special skill
+    pub held_skill: f32,
+    /// Weight of one HP point of difference (combat mode)
+    pub hp_difference: f32,
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        EvalWeights {
+            territory: 2.0,
+            atk_difference: 20.0,
+            bonus_proximity: 1.0,
+            held_skill: 5.0,
+            hp_difference: 30.0,
+        }
+    }
+}
+
+impl EvalWeights {
+    /// Weights as a vector, in declaration order (used by the tuner)
+    pub fn to_vec(&self) -> Vec<f32> {
+        vec![
+            self.territory,
+            self.atk_difference,
+            self.bonus_proximity,
+            self.held_skill,
+            self.hp_difference,
+        ]
+    }
+
+    /// Inverse of `to_vec`
+    pub fn from_slice(values: &[f32]) -> EvalWeights {
+        EvalWeights {
+            territory: values[0],
+            atk_difference: values[1],
+            bonus_proximity: values[2],
+            held_skill: values[3],
+            hp_difference: values[4],
+        }
+    }
+
+    /// Read weights from a TOML file, missing weights keep their default
+    pub fn load(path: &Path) -> io::Result<EvalWeights> {
+        toml::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    }
+
+    /// Write weights to a TOML file
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let content = toml::to_string(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        fs::write(path, content)
+    }
+}
+
+/// Distance of every tile from a starting coordinate (BFS)
+///
+/// # Parameters
+///
+/// * state: the state of the game
+/// * start: x, y starting coordinate
+///
+/// # Returns
+///
+/// Grid of distances, None if the tile can't be reached
+pub fn get_distance_map(state: &GameState, start: (usize, usize)) -> Vec<Vec<Option<u16>>> {
+    let size = state.get_height_width();
+    let mut distances = vec![vec![None; size]; size];
+    let mut queue = VecDeque::new();
+    distances[start.0][start.1] = Some(0);
+    queue.push_back(start);
+
+    let topology = state.get_topology();
+
+    while let Some((x, y)) = queue.pop_front() {
+        let dist = distances[x][y].unwrap_or(0);
+        for (_, (nx, ny)) in topology.get_neighbours((x, y)) {
+            if distances[nx][ny].is_none()
+                && is_passable_elem(state.get_elem_on_coor(nx, ny))
+            {
+                distances[nx][ny] = Some(dist + 1);
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    distances
+}
+
+/// Evaluate the state for the player
+///
+/// # Parameters
+///
+/// * state: the state of the game
+/// * player: id of player
+/// * weights: Weights of the evaluation terms
+///
+/// # Returns
+///
+/// Score of the state for the player. Terminal states are worth +-WIN_SCORE
+pub fn evaluate_state(state: &GameState, player: u8, weights: &EvalWeights) -> i32 {
+    if is_terminal_state(state) {
+        return terminal_score(state, player);
+    }
+
+    let opponents = state.get_enemies(player);
+    let team = state.get_team(player);
+    let mates: Vec<u8> = state
+        .get_active_players()
+        .into_iter()
+        .filter(|other| state.get_team(*other) == team)
+        .collect();
+    let own_dist = get_closest_distance_map(state, &mates);
+    let opp_dist = get_closest_distance_map(state, &opponents);
+
+    // Voronoi territory: tiles one player reaches strictly first
+    let mut territory = 0;
+    let mut own_bonus = None;
+    let mut opp_bonus = None;
+    for (x, row) in state.get_map_representation().iter().enumerate() {
+        for (y, elem) in row.iter().enumerate() {
+            match (own_dist[x][y], opp_dist[x][y]) {
+                (Some(a), Some(b)) if a < b => territory += 1,
+                (Some(a), Some(b)) if a > b => territory -= 1,
+                (Some(_), None) => territory += 1,
+                (None, Some(_)) => territory -= 1,
+                _ => (),
+            }
+            if *elem == GameElements::BonusATKBonusTile {
+                own_bonus = min_option(own_bonus, own_dist[x][y]);
+                opp_bonus = min_option(opp_bonus, opp_dist[x][y]);
+            }
+        }
+    }
+
+    let max_dist = (state.get_height_width() * 2) as i32;
+    let proximity = |dist: Option<u16>| dist.map_or(0, |d| max_dist - d as i32);
+    // own team value minus the one of the best opposing team
+    let difference = |value: &dyn Fn(u8) -> i32| {
+        let team_value = |team: u8| -> i32 {
+            state
+                .get_active_players()
+                .into_iter()
+                .filter(|other| state.get_team(*other) == team)
+                .map(value)
+                .sum()
+        };
+        let best_opponent = opponents
+            .iter()
+            .map(|opponent| team_value(state.get_team(*opponent)))
+            .max();
+        best_opponent.map_or(0, |best| team_value(team) - best)
+    };
+    let atk_difference = difference(&|p| i32::from(state.get_player_atk(p)));
+    let held_skill = difference(&|p| state.get_player_inventory(p).len() as i32);
+    let hp_difference = if state.get_rules().combat.enabled {
+        difference(&|p| i32::from(state.get_player_hp(p)))
+    } else {
+        0
+    };
+
+    let score = weights.territory * territory as f32
+        + weights.atk_difference * atk_difference as f32
+        + weights.bonus_proximity * (proximity(own_bonus) - proximity(opp_bonus)) as f32
+        + weights.held_skill * held_skill as f32
+        + weights.hp_difference * hp_difference as f32;
+    // keep heuristic scores below the terminal ones
+    (score.round() as i32).clamp(-WIN_SCORE / 2, WIN_SCORE / 2)
+}
+
+/// Distance of every tile from the closest of the players
+fn get_closest_distance_map(state: &GameState, players: &[u8]) -> Vec<Vec<Option<u16>>> {
+    let size = state.get_height_width();
+    let mut closest = vec![vec![None; size]; size];
+    for player in players {
+        let dist = get_distance_map(state, state.get_player_coor(*player));
+        for (x, row) in dist.iter().enumerate() {
+            for (y, d) in row.iter().enumerate() {
+                closest[x][y] = min_option(closest[x][y], *d);
+            }
+        }
+    }
+    closest
+}
+
+/// Score of a terminal state for the player, +-WIN_SCORE or 0
+pub fn terminal_score(state: &GameState, player: u8) -> i32 {
+    i32::from(get_result_for_player(state, player)) * WIN_SCORE
+}
+
+fn min_option(a: Option<u16>, b: Option<u16>) -> Option<u16> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
@@ -0,0 +1,369 @@
+//! Monte Carlo Tree Search Module
+//!
+//! Plain UCT with random rollouts.
+//! Nodes live in one arena `Vec` and refer to each other by index.
+//! A player without legal action is skipped when creating a node,
+//! so every non terminal node has a player that can move.
+//!
+//! In simultaneous mode the player can't see the actions of the round:
+//! the root actions are picked with UCB1 against random actions of the
+//! other players, then the game is played out by random rounds.
+//!
+//! In fog mode the search runs on several states drawn from the view of
+//! the player, the visits of the root actions are summed over the draws.
+//!
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::action::{get_legal_action, get_next_player_to_move, get_players_to_act, Action};
+use crate::fog::PlayerView;
+use crate::result::result_function::{result_after_action, result_after_round};
+use crate::state_repr::GameState;
+use crate::terminal::{get_result_for_player, is_terminal_state};
+
+/// Settings of the search
+#[derive(Debug, Clone, Copy)]
+pub struct MctsConfig {
+    /// Number of simulations (selection, expansion, rollout, backpropagation)
+    pub simulations: u32,
+    /// UCT exploration constant
+    pub exploration: f32,
+    /// States drawn from the view in fog mode, the simulations are split between them
+    pub determinizations: u32,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            simulations: 200,
+            exploration: 1.41,
+            determinizations: 4,
+        }
+    }
+}
+
+/// Result of the search at the root
+#[derive(Debug, Clone)]
+pub struct MctsResult {
+    /// Visit count of every legal action of the root, in `get_legal_action` order
+    pub action_visits: Vec<(Action, u32)>,
+    /// Most visited action, None if the player has no legal action
+    pub best_action: Option<Action>,
+}
+
+impl MctsResult {
+    /// Visit distribution (sums to 1) in the same order as `action_visits`
+    pub fn get_visit_distribution(&self) -> Vec<f32> {
+        let total: u32 = self.action_visits.iter().map(|(_, v)| v).sum();
+        self.action_visits
+            .iter()
+            .map(|(_, v)| {
+                if total == 0 {
+                    0.0
+                } else {
+                    *v as f32 / total as f32
+                }
+            })
+            .collect()
+    }
+}
+
+struct Node {
+    state: GameState,
+    to_move: u8,
+    /// Player and action that led to this node
+    action: Option<(u8, Action)>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Action>,
+    visits: u32,
+    /// Sum of the outcomes, seen from the player that played `action`
+    value: f32,
+}
+
+impl Node {
+    fn new(
+        state: GameState,
+        to_move: u8,
+        action: Option<(u8, Action)>,
+        parent: Option<usize>,
+        rng: &mut impl Rng,
+    ) -> Node {
+        // terminal nodes have nothing to expand
+        let mut untried = if is_terminal_state(&state) {
+            Vec::new()
+        } else {
+            get_legal_action(&state, to_move)
+        };
+        untried.shuffle(rng);
+        Node {
+            state,
+            to_move,
+            action,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            value: 0.0,
+        }
+    }
+}
+
+/// Search the state for the player
+///
+/// # Parameters
+///
+/// * state: the state of the game
+/// * player: Player to move
+/// * config: Search settings
+/// * rng: Random generator used for expansion and rollouts
+///
+/// # Returns
+///
+/// Visit counts of the root actions
+pub fn run_mcts(
+    state: &GameState,
+    player: u8,
+    config: &MctsConfig,
+    rng: &mut impl Rng,
+) -> MctsResult {
+    let mut nodes = vec![Node::new(state.clone(), player, None, None, rng)];
+
+    for _ in 0..config.simulations {
+        // Selection
+        let mut node_idx = 0;
+        while nodes[node_idx].untried.is_empty() && !nodes[node_idx].children.is_empty() {
+            node_idx = select_child(&nodes, node_idx, config.exploration);
+        }
+
+        // Expansion
+        if let Some(action) = nodes[node_idx].untried.pop() {
+            let mover = nodes[node_idx].to_move;
+            let next_state = result_after_action(&nodes[node_idx].state, &action, mover);
+            let next_player = get_next_player_to_move(&next_state, mover);
+            let child = Node::new(
+                next_state,
+                next_player,
+                Some((mover, action)),
+                Some(node_idx),
+                rng,
+            );
+            nodes.push(child);
+            let child_idx = nodes.len() - 1;
+            nodes[node_idx].children.push(child_idx);
+            node_idx = child_idx;
+        }
+
+        // Rollout
+        let scores = random_rollout(&nodes[node_idx].state, nodes[node_idx].to_move, rng);
+
+        // Backpropagation
+        let mut current = Some(node_idx);
+        while let Some(idx) = current {
+            let node = &mut nodes[idx];
+            node.visits += 1;
+            if let Some((mover, _)) = node.action {
+                node.value += scores[mover as usize];
+            }
+            current = node.parent;
+        }
+    }
+
+    let action_visits: Vec<(Action, u32)> = get_legal_action(state, player)
+        .into_iter()
+        .map(|action| {
+            let visits = nodes[0]
+                .children
+                .iter()
+                .find(|&&c| matches!(nodes[c].action, Some((_, a)) if a == action))
+                .map_or(0, |&c| nodes[c].visits);
+            (action, visits)
+        })
+        .collect();
+    let best_action = action_visits
+        .iter()
+        .reduce(|best, other| if other.1 > best.1 { other } else { best })
+        .map(|(action, _)| *action);
+
+    MctsResult {
+        action_visits,
+        best_action,
+    }
+}
+
+/// Search the state for the player in simultaneous mode
+///
+/// # Parameters
+///
+/// * state: the state of the game at the start of the round
+/// * player: Player to act
+/// * config: Search settings
+/// * rng: Random generator used for the other players and rollouts
+///
+/// # Returns
+///
+/// Visit counts of the root actions
+pub fn run_simultaneous_mcts(
+    state: &GameState,
+    player: u8,
+    config: &MctsConfig,
+    rng: &mut impl Rng,
+) -> MctsResult {
+    let legal_actions = get_legal_action(state, player);
+    let mut visits = vec![0_u32; legal_actions.len()];
+    let mut values = vec![0.0_f32; legal_actions.len()];
+
+    for simulation in 0..config.simulations {
+        if legal_actions.is_empty() {
+            break;
+        }
+        // UCB1 over the root actions, unvisited ones first
+        let parent_visits = (simulation.max(1)) as f32;
+        let ucb = |idx: usize| {
+            if visits[idx] == 0 {
+                return f32::INFINITY;
+            }
+            let idx_visits = visits[idx] as f32;
+            values[idx] / idx_visits + config.exploration * (parent_visits.ln() / idx_visits).sqrt()
+        };
+        let chosen = (0..legal_actions.len())
+            .reduce(|best, other| if ucb(other) > ucb(best) { other } else { best })
+            .expect("The player has a legal action");
+
+        let actions = get_random_round(state, rng, Some((player, legal_actions[chosen])));
+        let next_state = result_after_round(state, &actions);
+        let scores = random_round_rollout(&next_state, rng);
+        visits[chosen] += 1;
+        values[chosen] += scores[player as usize];
+    }
+
+    let action_visits: Vec<(Action, u32)> = legal_actions.into_iter().zip(visits).collect();
+    let best_action = action_visits
+        .iter()
+        .reduce(|best, other| if other.1 > best.1 { other } else { best })
+        .map(|(action, _)| *action);
+    MctsResult {
+        action_visits,
+        best_action,
+    }
+}
+
+/// Search the view of the player in fog mode
+///
+/// # Parameters
+///
+/// * view: what the player knows about the game
+/// * config: Search settings
+/// * rng: Random generator used for the draws, expansion and rollouts
+///
+/// # Returns
+///
+/// Visit counts of the legal actions of the view, summed over the draws
+pub fn run_determinized_mcts(
+    view: &PlayerView,
+    config: &MctsConfig,
+    rng: &mut impl Rng,
+) -> MctsResult {
+    let draws = config.determinizations.max(1);
+    let draw_config = MctsConfig {
+        simulations: (config.simulations / draws).max(1),
+        ..*config
+    };
+    let mut action_visits: Vec<(Action, u32)> =
+        view.legal_actions.iter().map(|action| (*action, 0)).collect();
+    for _ in 0..draws {
+        let state = view.determinize(rng);
+        let result = if view.rules.turns.simultaneous {
+            run_simultaneous_mcts(&state, view.player, &draw_config, rng)
+        } else {
+            run_mcts(&state, view.player, &draw_config, rng)
+        };
+        for (action, visits) in result.action_visits {
+            if let Some(entry) = action_visits.iter_mut().find(|(a, _)| *a == action) {
+                entry.1 += visits;
+            }
+        }
+    }
+
+    let best_action = action_visits
+        .iter()
+        .reduce(|best, other| if other.1 > best.1 { other } else { best })
+        .map(|(action, _)| *action);
+    MctsResult {
+        action_visits,
+        best_action,
+    }
+}
+
+/// Random legal action of every player of the round, a forced player plays its action
+fn get_random_round(
+    state: &GameState,
+    rng: &mut impl Rng,
+    forced: Option<(u8, Action)>,
+) -> Vec<(u8, Action)> {
+    get_players_to_act(state)
+        .into_iter()
+        .filter_map(|player| match forced {
+            Some((forced_player, action)) if forced_player == player => Some((player, action)),
+            _ => get_legal_action(state, player).choose(rng).map(|action| (player, *action)),
+        })
+        .collect()
+}
+
+/// Play random rounds until the game ends
+///
+/// # Returns
+///
+/// Outcome for each player: 1 win, -1 lose, 0 draw
+fn random_round_rollout(state: &GameState, rng: &mut impl Rng) -> Vec<f32> {
+    let mut state = state.clone();
+    while !is_terminal_state(&state) {
+        let actions = get_random_round(&state, rng, None);
+        if actions.is_empty() {
+            break;
+        }
+        state = result_after_round(&state, &actions);
+    }
+    (0..state.get_player_count() as u8)
+        .map(|player| f32::from(get_result_for_player(&state, player)))
+        .collect()
+}
+
+/// Child with the highest UCT value
+fn select_child(nodes: &[Node], node_idx: usize, exploration: f32) -> usize {
+    let parent_visits = nodes[node_idx].visits.max(1) as f32;
+    let uct = |idx: usize| {
+        let child = &nodes[idx];
+        if child.visits == 0 {
+            return f32::INFINITY;
+        }
+        let visits = child.visits as f32;
+        child.value / visits + exploration * (parent_visits.ln() / visits).sqrt()
+    };
+    nodes[node_idx]
+        .children
+        .iter()
+        .copied()
+        .reduce(|best, other| if uct(other) > uct(best) { other } else { best })
+        .expect("Selection only happens on nodes with children")
+}
+
+/// Play random legal actions until the game ends
+///
+/// # Returns
+///
+/// Outcome for each player: 1 win, -1 lose, 0 draw
+fn random_rollout(state: &GameState, to_move: u8, rng: &mut impl Rng) -> Vec<f32> {
+    let mut state = state.clone();
+    let mut player = to_move;
+    while !is_terminal_state(&state) {
+        let legal_actions = get_legal_action(&state, player);
+        if let Some(action) = legal_actions.choose(rng) {
+            state = result_after_action(&state, action, player);
+        }
+        player = (player + 1) % state.get_player_count() as u8;
+    }
+    (0..state.get_player_count() as u8)
+        .map(|player| f32::from(get_result_for_player(&state, player)))
+        .collect()
+}
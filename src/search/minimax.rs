@@ -0,0 +1,301 @@
+//! Minimax Search Module
+//!
+//! Depth limited minimax with alpha-beta pruning.
+//! The root player maximizes, the opponents minimize.
+//! With more than 2 players the opponents are assumed to play together
+//! against the root player (paranoid search), team-mates of the root player maximize too.
+//! A player without legal action passes the turn, just like on the CLI.
+//!
+//! In simultaneous mode the search goes round by round: the team of the root
+//! player commits its actions, then the enemies answer with the worst joint
+//! action for it (maximin), so the root player never relies on seeing a move.
+//!
+use crate::action::{get_legal_action, get_players_to_act, Action};
+use crate::result::result_function::{result_after_action, result_after_round};
+use crate::search::evaluation::{evaluate_state, EvalWeights, WIN_SCORE};
+use crate::state_repr::GameState;
+use crate::terminal::is_terminal_state;
+
+/// Default search depth (in plies)
+pub const DEFAULT_SEARCH_DEPTH: u8 = 6;
+
+/// Result of the search for one action
+#[derive(Debug, Clone)]
+pub struct ActionAnalysis {
+    pub action: Action,
+    pub score: i32,
+    /// Best line after the action (the action included), as (player, action)
+    pub principal_variation: Vec<(u8, Action)>,
+}
+
+/// Search every legal action of the player
+///
+/// # Parameters
+///
+/// * state: the state of the game
+/// * player: Player
+/// * depth: Search depth in plies
+/// * weights: Weights of the evaluation
+///
+/// # Returns
+///
+/// Analysis of every action in `get_legal_action` order.
+/// Empty if the player has no legal action
+pub fn analyze_legal_actions(
+    state: &GameState,
+    player: u8,
+    depth: u8,
+    weights: &EvalWeights,
+) -> Vec<ActionAnalysis> {
+    get_legal_action(state, player)
+        .into_iter()
+        .map(|action| {
+            let next_state = result_after_action(state, &action, player);
+            let (score, mut line) = minimax(
+                &next_state,
+                get_next_seat(state, player),
+                player,
+                depth.saturating_sub(1),
+                -WIN_SCORE - 1,
+                WIN_SCORE + 1,
+                weights,
+            );
+            line.insert(0, (player, action));
+            ActionAnalysis {
+                action,
+                score,
+                principal_variation: line,
+            }
+        })
+        .collect()
+}
+
+/// Pick the highest scored analysis, first one wins ties
+pub fn get_best_analysis(analyses: &[ActionAnalysis]) -> Option<&ActionAnalysis> {
+    analyses
+        .iter()
+        .reduce(|best, other| if other.score > best.score { other } else { best })
+}
+
+/// Alpha-beta minimax
+///
+/// # Parameters
+///
+/// * state: the state of the game
+/// * to_move: Player that moves in this state
+/// * root_player: Player the score is computed for
+/// * depth: Remaining depth
+/// * alpha, beta: Alpha-beta window
+/// * weights: Weights of the evaluation
+///
+/// # Returns
+///
+/// Score and principal variation from this state
+fn minimax(
+    state: &GameState,
+    to_move: u8,
+    root_player: u8,
+    depth: u8,
+    mut alpha: i32,
+    mut beta: i32,
+    weights: &EvalWeights,
+) -> (i32, Vec<(u8, Action)>) {
+    if depth == 0 || is_terminal_state(state) {
+        return (evaluate_state(state, root_player, weights), Vec::new());
+    }
+
+    let legal_actions = get_legal_action(state, to_move);
+    if legal_actions.is_empty() {
+        // No valid move, SKIP turn
+        return minimax(
+            state,
+            get_next_seat(state, to_move),
+            root_player,
+            depth - 1,
+            alpha,
+            beta,
+            weights,
+        );
+    }
+
+    let maximizing = state.get_team(to_move) == state.get_team(root_player);
+    let mut best_score = if maximizing { i32::MIN } else { i32::MAX };
+    let mut best_line = Vec::new();
+
+    for action in legal_actions {
+        let next_state = result_after_action(state, &action, to_move);
+        let (score, mut line) = minimax(
+            &next_state,
+            get_next_seat(state, to_move),
+            root_player,
+            depth - 1,
+            alpha,
+            beta,
+            weights,
+        );
+        // prefer quicker wins and slower losses
+        let score = score - score.signum() * i32::from(score.abs() >= WIN_SCORE - 100);
+
+        let improved = if maximizing {
+            score > best_score
+        } else {
+            score < best_score
+        };
+        if improved {
+            best_score = score;
+            line.insert(0, (to_move, action));
+            best_line = line;
+        }
+
+        if maximizing {
+            alpha = alpha.max(best_score);
+        } else {
+            beta = beta.min(best_score);
+        }
+        if alpha >= beta {
+            break;
+        }
+    }
+    (best_score, best_line)
+}
+
+/// Player sitting after the player, whether it can move or not
+fn get_next_seat(state: &GameState, player: u8) -> u8 {
+    (player + 1) % state.get_player_count() as u8
+}
+
+/// Search every legal action of the player in simultaneous mode
+///
+/// # Parameters
+///
+/// * state: the state of the game at the start of the round
+/// * player: Player
+/// * depth: Search depth in plies, every player of a round plays one ply
+/// * weights: Weights of the evaluation
+///
+/// # Returns
+///
+/// Analysis of every action in `get_legal_action` order, the principal
+/// variation holds the actions of every player round by round.
+/// Empty if the player has no legal action
+pub fn analyze_simultaneous_actions(
+    state: &GameState,
+    player: u8,
+    depth: u8,
+    weights: &EvalWeights,
+) -> Vec<ActionAnalysis> {
+    let rounds = (depth as usize / state.get_player_count()).max(1);
+    get_legal_action(state, player)
+        .into_iter()
+        .map(|action| {
+            let (score, principal_variation) = maximin_round(
+                state,
+                player,
+                Some((player, action)),
+                rounds,
+                -WIN_SCORE - 1,
+                WIN_SCORE + 1,
+                weights,
+            );
+            ActionAnalysis {
+                action,
+                score,
+                principal_variation,
+            }
+        })
+        .collect()
+}
+
+/// Alpha-beta maximin over the joint actions of a round
+///
+/// # Parameters
+///
+/// * state: the state of the game at the start of the round
+/// * root_player: Player the score is computed for
+/// * forced: Action already chosen for a player of the round
+/// * rounds: Remaining rounds
+/// * alpha, beta: Alpha-beta window
+/// * weights: Weights of the evaluation
+///
+/// # Returns
+///
+/// Score and principal variation from this state
+fn maximin_round(
+    state: &GameState,
+    root_player: u8,
+    forced: Option<(u8, Action)>,
+    rounds: usize,
+    mut alpha: i32,
+    beta: i32,
+    weights: &EvalWeights,
+) -> (i32, Vec<(u8, Action)>) {
+    if rounds == 0 || is_terminal_state(state) {
+        return (evaluate_state(state, root_player, weights), Vec::new());
+    }
+    let team = state.get_team(root_player);
+    let (own, enemies): (Vec<u8>, Vec<u8>) = get_players_to_act(state)
+        .into_iter()
+        .partition(|player| state.get_team(*player) == team);
+
+    let mut best_score = i32::MIN;
+    let mut best_line = Vec::new();
+    for own_actions in get_joint_actions(state, &own, forced) {
+        let mut worst_score = i32::MAX;
+        let mut worst_line = Vec::new();
+        for enemy_actions in get_joint_actions(state, &enemies, None) {
+            let mut actions = [own_actions.clone(), enemy_actions].concat();
+            actions.sort_by_key(|(player, _)| *player);
+            let next_state = result_after_round(state, &actions);
+            let (score, line) = maximin_round(
+                &next_state,
+                root_player,
+                None,
+                rounds - 1,
+                alpha.max(best_score),
+                beta.min(worst_score),
+                weights,
+            );
+            // prefer quicker wins and slower losses
+            let score = score - score.signum() * i32::from(score.abs() >= WIN_SCORE - 100);
+            if score < worst_score {
+                worst_score = score;
+                worst_line = [actions, line].concat();
+            }
+            if worst_score <= alpha.max(best_score) {
+                break;
+            }
+        }
+        if worst_score > best_score {
+            best_score = worst_score;
+            best_line = worst_line;
+        }
+        alpha = alpha.max(best_score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    (best_score, best_line)
+}
+
+/// Every combination of one legal action per player.
+/// A forced player only plays its forced action
+fn get_joint_actions(
+    state: &GameState,
+    players: &[u8],
+    forced: Option<(u8, Action)>,
+) -> Vec<Vec<(u8, Action)>> {
+    let mut joint_actions = vec![Vec::new()];
+    for player in players {
+        let actions = match forced {
+            Some((forced_player, action)) if forced_player == *player => vec![action],
+            _ => get_legal_action(state, *player),
+        };
+        joint_actions = joint_actions
+            .into_iter()
+            .flat_map(|joint: Vec<(u8, Action)>| {
+                actions.iter().map(move |action| [joint.clone(), vec![(*player, *action)]].concat())
+            })
+            .collect();
+    }
+    joint_actions
+}
@@ -0,0 +1,3 @@
+pub mod evaluation;
+pub mod minimax;
+pub mod mcts;
@@ -0,0 +1,298 @@
+//! Self-play Module
+//!
+//! Plays MCTS vs MCTS games in parallel and writes training data as CSV shards.
+//!
+//! Every game has its own seed (`seed + game index`) used for the map and the search,
+//! so a game can always be replayed. Games are grouped in shards of `shard_size`
+//! games, one file per shard. A shard is written to a temporary file and renamed
+//! once complete, so finished shards are skipped when a run is resumed.
+//!
+//! # CSV columns
+//!
+//! `game,ply,player,action,visit_0..visit_N,outcome,feature_0..feature_M`
+//!
+//! * action: index in the action space of the rules (`get_action_space`)
+//! * visit_*: MCTS visit distribution over the action space
+//! * outcome: final result for `player`, 1 win, -1 lose, 0 draw
+//! * feature_*: flattened `Observation` (planes then features)
+//!
+//! In fog mode the players search and are encoded from their own view
+//! (`run_determinized_mcts`, `encode_view`). In simultaneous mode every player
+//! searches the state at the start of the round, then `result_after_round` resolves it.
+//!
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::action::{
+    get_first_player_to_move, get_next_player_to_move, get_players_to_act, Action,
+};
+use crate::environment::{action_to_index, encode_observation, encode_view, get_action_space_size};
+use crate::fog::{get_fog_memories, remember_all, FogMemory};
+use crate::result::result_function::{result_after_action, result_after_round};
+use crate::rules::RuleSet;
+use crate::search::mcts::{run_determinized_mcts, run_mcts, run_simultaneous_mcts, MctsConfig};
+use crate::state_repr::{GameState, MapRandom};
+use crate::terminal::{get_result_for_player, is_terminal_state};
+
+/// Settings of a self-play run
+#[derive(Debug, Clone)]
+pub struct SelfPlayConfig {
+    pub games: usize,
+    pub threads: usize,
+    pub seed: u64,
+    pub shard_size: usize,
+    pub rules: RuleSet,
+    pub mcts: MctsConfig,
+    pub output_dir: PathBuf,
+}
+
+impl Default for SelfPlayConfig {
+    fn default() -> Self {
+        SelfPlayConfig {
+            games: 100,
+            threads: 4,
+            seed: 0,
+            shard_size: 10,
+            rules: RuleSet::with_board_size(5),
+            mcts: MctsConfig::default(),
+            output_dir: PathBuf::from("selfplay_data"),
+        }
+    }
+}
+
+/// What a run did
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SelfPlaySummary {
+    pub shards_written: usize,
+    pub shards_skipped: usize,
+    pub positions_written: usize,
+}
+
+/// One recorded decision of a game
+struct PositionRecord {
+    ply: u32,
+    player: u8,
+    action_index: usize,
+    visit_distribution: Vec<f32>,
+    features: Vec<f32>,
+}
+
+/// Path of a shard file
+pub fn get_shard_path(output_dir: &Path, shard: usize) -> PathBuf {
+    output_dir.join(format!("shard_{:05}.csv", shard))
+}
+
+/// Run the self-play, shards already on disk are skipped
+///
+/// # Parameters
+///
+/// * config: Settings of the run
+///
+/// # Returns
+///
+/// Summary of the run or the first IO error met
+pub fn run_selfplay(config: &SelfPlayConfig) -> io::Result<SelfPlaySummary> {
+    fs::create_dir_all(&config.output_dir)?;
+    let shard_size = config.shard_size.max(1);
+    let shard_count = config.games.div_ceil(shard_size);
+    let next_shard = AtomicUsize::new(0);
+    let summary = Mutex::new(SelfPlaySummary::default());
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..config.threads.max(1))
+            .map(|_| {
+                scope.spawn(|| -> io::Result<()> {
+                    loop {
+                        let shard = next_shard.fetch_add(1, Ordering::SeqCst);
+                        if shard >= shard_count {
+                            return Ok(());
+                        }
+                        let path = get_shard_path(&config.output_dir, shard);
+                        if path.exists() {
+                            summary.lock().unwrap().shards_skipped += 1;
+                            continue;
+                        }
+                        let first_game = shard * shard_size;
+                        let last_game = (first_game + shard_size).min(config.games);
+                        let positions = write_shard(config, &path, first_game..last_game)?;
+                        let mut summary = summary.lock().unwrap();
+                        summary.shards_written += 1;
+                        summary.positions_written += positions;
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .try_for_each(|worker| worker.join().expect("Self-play worker panicked"))
+    })?;
+
+    Ok(summary.into_inner().unwrap())
+}
+
+/// Play the games of a shard and write them, returns the number of positions
+fn write_shard(
+    config: &SelfPlayConfig,
+    path: &Path,
+    games: std::ops::Range<usize>,
+) -> io::Result<usize> {
+    let tmp_path = path.with_extension("csv.tmp");
+    let mut writer = BufWriter::new(fs::File::create(&tmp_path)?);
+    let mut positions = 0;
+    let mut header_written = false;
+
+    for game in games {
+        let game_seed = config.seed.wrapping_add(game as u64);
+        let (records, final_state) = play_selfplay_game(config, game_seed);
+        for record in &records {
+            if !header_written {
+                write_header(
+                    &mut writer,
+                    record.visit_distribution.len(),
+                    record.features.len(),
+                )?;
+                header_written = true;
+            }
+            let outcome = get_result_for_player(&final_state, record.player);
+            let mut row = vec![
+                game.to_string(),
+                record.ply.to_string(),
+                record.player.to_string(),
+                record.action_index.to_string(),
+            ];
+            row.extend(
+                record
+                    .visit_distribution
+                    .iter()
+                    .map(|v| format!("{:.4}", v)),
+            );
+            row.push(outcome.to_string());
+            row.extend(record.features.iter().map(|v| v.to_string()));
+            writeln!(writer, "{}", row.join(","))?;
+        }
+        positions += records.len();
+    }
+
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&tmp_path, path)?;
+    Ok(positions)
+}
+
+fn write_header(
+    writer: &mut impl Write,
+    action_count: usize,
+    feature_count: usize,
+) -> io::Result<()> {
+    let mut header = vec![
+        "game".to_string(),
+        "ply".into(),
+        "player".into(),
+        "action".into(),
+    ];
+    header.extend((0..action_count).map(|i| format!("visit_{}", i)));
+    header.push("outcome".into());
+    header.extend((0..feature_count).map(|i| format!("feature_{}", i)));
+    writeln!(writer, "{}", header.join(","))
+}
+
+/// Play one MCTS vs MCTS game.
+/// In simultaneous mode every player searches the state at the start of the round.
+///
+/// # Returns
+///
+/// Every decision of the game and the final state
+fn play_selfplay_game(config: &SelfPlayConfig, game_seed: u64) -> (Vec<PositionRecord>, GameState) {
+    let mut rng = StdRng::seed_from_u64(game_seed);
+    let mut state = GameState::new_with_rules(&config.rules, MapRandom::FullyRandom, game_seed);
+    let mut memories = get_fog_memories(&state);
+    let mut player = get_first_player_to_move(&state);
+    let mut ply = 0;
+    let mut records = Vec::new();
+
+    while !is_terminal_state(&state) {
+        if state.get_rules().turns.simultaneous {
+            let mut actions = Vec::new();
+            for player in get_players_to_act(&state) {
+                let (action, record) =
+                    search_position(config, &state, player, ply, &mut memories, &mut rng);
+                actions.push((player, action));
+                records.push(record);
+                ply += 1;
+            }
+            if actions.is_empty() {
+                break;
+            }
+            state = result_after_round(&state, &actions);
+        } else {
+            let (action, record) =
+                search_position(config, &state, player, ply, &mut memories, &mut rng);
+            records.push(record);
+            state = result_after_action(&state, &action, player);
+            player = get_next_player_to_move(&state, player);
+            ply += 1;
+        }
+        remember_all(&mut memories, &state);
+    }
+    (records, state)
+}
+
+/// Search the position for the player, from its view in fog mode
+///
+/// # Returns
+///
+/// The best action and its record
+fn search_position(
+    config: &SelfPlayConfig,
+    state: &GameState,
+    player: u8,
+    ply: u32,
+    memories: &mut [FogMemory],
+    rng: &mut StdRng,
+) -> (Action, PositionRecord) {
+    let (result, observation) = match memories.get_mut(player as usize) {
+        Some(memory) => {
+            let view = memory.observe(state);
+            (
+                run_determinized_mcts(&view, &config.mcts, rng),
+                encode_view(&view),
+            )
+        }
+        None if state.get_rules().turns.simultaneous => (
+            run_simultaneous_mcts(state, player, &config.mcts, rng),
+            encode_observation(state, player),
+        ),
+        None => (
+            run_mcts(state, player, &config.mcts, rng),
+            encode_observation(state, player),
+        ),
+    };
+    let action = result
+        .best_action
+        .expect("Non terminal player always has an action");
+
+    let rules = state.get_rules();
+    let mut visit_distribution = vec![0.0; get_action_space_size(rules)];
+    for ((visited, _), probability) in result
+        .action_visits
+        .iter()
+        .zip(result.get_visit_distribution())
+    {
+        visit_distribution[action_to_index(visited, rules)] = probability;
+    }
+    let record = PositionRecord {
+        ply,
+        player,
+        action_index: action_to_index(&action, rules),
+        visit_distribution,
+        features: observation.to_flat_vec(),
+    };
+    (action, record)
+}
@@ -0,0 +1,201 @@
+//! Special Skill Module
+//!
+//! The special skills, the tiles giving them and the targets they can reach.
+//! Target helpers are shared by the legal action generation and the skill effects.
+//!
+use strum_macros::{EnumCount as EnumCountMacro, EnumIter};
+
+use crate::action::{Direction, SkillTarget};
+use crate::fog::is_visible;
+use crate::state_repr::{is_passable_elem, GameElements, GameState};
+
+///
+/// Enum for special skill .
+///
+#[derive(PartialEq, Debug, Clone, EnumCountMacro, EnumIter)]
+pub enum SpecialSkill {
+    None,
+    ReduceEnemiesATK,
+    ClearRoadTile,
+    /// Jump to a passable tile within `teleport_range`
+    Teleport,
+    /// Put a `NonPassable` tile next to the enemy
+    PlaceWall,
+    /// Swap position with the enemy
+    Swap,
+    /// Move two tiles in a straight line
+    Dash,
+}
+
+/// Special skill given by a tile, None if the tile gives no skill
+pub fn get_sp_skill_of_tile(elem: &GameElements) -> Option<SpecialSkill> {
+    match elem {
+        GameElements::MinusATKBonusSpTile => Some(SpecialSkill::ReduceEnemiesATK),
+        GameElements::ClearRoadBonusSpTile => Some(SpecialSkill::ClearRoadTile),
+        GameElements::TeleportBonusSpTile => Some(SpecialSkill::Teleport),
+        GameElements::WallBonusSpTile => Some(SpecialSkill::PlaceWall),
+        GameElements::SwapBonusSpTile => Some(SpecialSkill::Swap),
+        GameElements::DashBonusSpTile => Some(SpecialSkill::Dash),
+        _ => None,
+    }
+}
+
+/// Tile one step away in the direction, None if out of bound
+pub fn get_offset_coor(
+    state: &GameState,
+    coor: (usize, usize),
+    direction: Direction,
+) -> Option<(usize, usize)> {
+    state.get_topology().get_neighbour(coor, direction)
+}
+
+/// Whether a player can stand on the tile
+fn is_passable(state: &GameState, coor: (usize, usize)) -> bool {
+    is_passable_elem(state.get_elem_on_coor(coor.0, coor.1))
+}
+
+/// Directions with a non passable tile next to the player, the void can't be cleared
+pub fn get_clear_road_targets(state: &GameState, player: u8) -> Vec<Direction> {
+    let own = state.get_player_coor(player);
+    state
+        .get_topology()
+        .get_neighbours(own)
+        .into_iter()
+        .filter(|(_, coor)| *state.get_elem_on_coor(coor.0, coor.1) == GameElements::NonPassable)
+        .map(|(direction, _)| direction)
+        .collect()
+}
+
+/// Passable tiles within `teleport_range` of the player, enemies excluded.
+/// In fog mode only the tiles the player sees.
+pub fn get_teleport_targets(state: &GameState, player: u8) -> Vec<(usize, usize)> {
+    let own = state.get_player_coor(player);
+    let range = state.get_rules().skills.teleport_range;
+    let topology = state.get_topology();
+    let mut targets = Vec::new();
+    for x in 0..state.get_height_width() {
+        for y in 0..state.get_height_width() {
+            let distance = topology.get_distance(own, (x, y));
+            let occupied = state.get_enemy_on_coor(player, (x, y)).is_some();
+            if distance > 0
+                && distance <= range
+                && !occupied
+                && is_passable(state, (x, y))
+                && is_visible(state, player, (x, y))
+            {
+                targets.push((x, y));
+            }
+        }
+    }
+    targets
+}
+
+/// Passable tiles next to an enemy where a wall can be placed, players excluded.
+/// In fog mode only the tiles the player sees, around the enemies it sees.
+pub fn get_wall_targets(state: &GameState, player: u8) -> Vec<(usize, usize)> {
+    let mut targets = Vec::new();
+    for enemy in get_visible_enemies(state, player) {
+        let enemy_coor = state.get_player_coor(enemy);
+        for (_, coor) in state.get_topology().get_neighbours(enemy_coor) {
+            let free = state.get_player_on_coor(coor).is_none() && is_passable(state, coor);
+            if free && is_visible(state, player, coor) && !targets.contains(&coor) {
+                targets.push(coor);
+            }
+        }
+    }
+    targets
+}
+
+/// Enemies the player sees, all of them without fog
+fn get_visible_enemies(state: &GameState, player: u8) -> Vec<u8> {
+    state
+        .get_enemies(player)
+        .into_iter()
+        .filter(|enemy| is_visible(state, player, state.get_player_coor(*enemy)))
+        .collect()
+}
+
+/// Enemies a `Swap` can aim at. With a single enemy the skill needs no aim,
+/// otherwise it is aimed at the tile of the enemy.
+/// In fog mode only the enemies the player sees.
+pub fn get_swap_targets(state: &GameState, player: u8) -> Vec<SkillTarget> {
+    let enemies = get_visible_enemies(state, player);
+    if enemies.len() == 1 && state.get_enemies(player).len() == 1 {
+        return vec![SkillTarget::None];
+    }
+    enemies
+        .into_iter()
+        .map(|enemy| {
+            let (x, y) = state.get_player_coor(enemy);
+            SkillTarget::Coordinate(x, y)
+        })
+        .collect()
+}
+
+/// A dash the player can do
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DashTarget {
+    pub direction: Direction,
+    /// Tile run through
+    pub passed: (usize, usize),
+    /// Tile landed on
+    pub landing: (usize, usize),
+}
+
+/// Dashes available to the player, in the direction order of the grid.
+/// In fog mode only onto the tiles the player sees.
+pub fn get_dash_targets(state: &GameState, player: u8) -> Vec<DashTarget> {
+    let own = state.get_player_coor(player);
+    state
+        .get_topology()
+        .get_directions()
+        .iter()
+        .filter_map(|direction| {
+            let direction = *direction;
+            let passed = get_offset_coor(state, own, direction)?;
+            let landing = get_offset_coor(state, passed, direction)?;
+            if is_passable(state, passed)
+                && is_passable(state, landing)
+                && is_visible(state, player, landing)
+            {
+                Some(DashTarget {
+                    direction,
+                    passed,
+                    landing,
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Every valid target of a skill used by the player.
+/// Empty for `SpecialSkill::None` or if the skill has nothing to aim at.
+pub fn get_sp_skill_targets(
+    state: &GameState,
+    player: u8,
+    skill: &SpecialSkill,
+) -> Vec<SkillTarget> {
+    let coordinates = |targets: Vec<(usize, usize)>| {
+        targets
+            .into_iter()
+            .map(|(x, y)| SkillTarget::Coordinate(x, y))
+            .collect()
+    };
+    match skill {
+        SpecialSkill::None => Vec::new(),
+        SpecialSkill::ReduceEnemiesATK => vec![SkillTarget::None],
+        SpecialSkill::Swap => get_swap_targets(state, player),
+        SpecialSkill::ClearRoadTile => get_clear_road_targets(state, player)
+            .into_iter()
+            .map(SkillTarget::Direction)
+            .collect(),
+        SpecialSkill::Dash => get_dash_targets(state, player)
+            .into_iter()
+            .map(|dash| SkillTarget::Direction(dash.direction))
+            .collect(),
+        SpecialSkill::Teleport => coordinates(get_teleport_targets(state, player)),
+        SpecialSkill::PlaceWall => coordinates(get_wall_targets(state, player)),
+    }
+}
@@ -6,25 +6,31 @@
 //! Any state related things goes here
 //!
 //!
-use rand::Rng;
-use strum::EnumCount;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 use strum_macros::{Display, EnumCount as EnumCountMacro, EnumIter};
 
-///
-/// Enum for special skill .
-/// TODO move out
-///
-#[derive(PartialEq, Debug, Clone)]
-pub enum SpecialSkill {
-    None,
-    ReduceEnemiesATK,
-    ClearRoadTile,
-}
+use crate::arena::get_next_collapse;
+use crate::map::GameMap;
+use crate::map_generation::generate_walls;
+use crate::respawn::get_next_spawn;
+use crate::rules::{GenerationRules, PickupPolicy, RuleSet};
+use crate::skill::SpecialSkill;
+use crate::topology::Topology;
 
 /// Map Randomness Setting enum
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum MapRandom {
     FullyRandom,
+    /// Cellular automata caves
+    Caves,
+    /// Maze with loops
+    Maze,
+    /// Rooms joined by corridors
+    Rooms,
 }
 
 /// Game Elements in the game
@@ -34,9 +40,30 @@ pub enum GameElements {
     NonPassable,
     PlayerOne,
     PlayerTwo,
+    PlayerThree,
+    PlayerFour,
     BonusATKBonusTile,
     MinusATKBonusSpTile,
     ClearRoadBonusSpTile,
+    TeleportBonusSpTile,
+    WallBonusSpTile,
+    SwapBonusSpTile,
+    DashBonusSpTile,
+    /// Sends the player to the other teleporter of its pair
+    TeleporterTile,
+    /// The player slides until blocked
+    IceTile,
+    /// Pushes the player one tile up
+    ConveyorUpTile,
+    ConveyorDownTile,
+    ConveyorRightTile,
+    ConveyorLeftTile,
+    /// The player skips its next turn
+    SkipTrapTile,
+    /// The player loses ATK
+    ATKTrapTile,
+    /// Outside the board (irregular maps), never passable nor cleared
+    Void,
 }
 
 /// This struct represents the game state
@@ -46,24 +73,65 @@ pub enum GameElements {
 /// It contains a 2D array. Each array may contains:
 /// 'o': passable tile
 /// 'x': non-passable tile
-/// '1': first player
-/// '2': second player
+/// '1', '2', '3', '4': players
 /// '+': Bonus ATK +1
 /// '-': Minus ATK for enemy -1
 /// 'c': Clear Road for all direction
+/// 't': Teleport within range
+/// 'w': Wall next to the enemy
+/// 's': Swap position with the enemy
+/// 'd': Dash two tiles straight
+/// '@': Teleporter, paired in column order (1st with 2nd, 3rd with 4th...)
+/// '*': Ice, slide until blocked
+/// '^', 'v', '>', '<': Conveyor pushing one tile
+/// '!': Trap, skip the next turn
+/// '%': Trap, lose ATK
+/// '.': Void, outside of an irregular map
 ///
 #[derive(Debug, Clone)]
 pub struct GameState {
+    rules: Arc<RuleSet>,
     height_width: usize,
     map_representation: Vec<Vec<GameElements>>,
-    first_player_pos: (usize, usize),
-    second_player_pos: (usize, usize),
-    first_player_sp: SpecialSkill,
-    second_player_sp: SpecialSkill,
-    first_player_atk: i16,
-    second_player_atk: i16,
+    players: Vec<PlayerState>,
+    turn_count: u32,
+    seed: u64,
+    /// Teleporters linked to each other, a pair is removed when one of its tiles is covered
+    teleporter_pairs: Vec<TeleporterPair>,
+    /// Generator of the map, None for a hand-authored map
+    map_randomness: Option<MapRandom>,
 }
 
+/// Coordinates of the two teleporters of a pair
+pub type TeleporterPair = [(usize, usize); 2];
+
+/// Everything about one player
+#[derive(Debug, Clone)]
+struct PlayerState {
+    position: (usize, usize),
+    inventory: Vec<SpecialSkill>,
+    atk: i16,
+    /// Only used in combat mode
+    hp: i16,
+    /// Number of enemy actions before the player can act again (traps)
+    wait: u8,
+    /// Caught by a collapsing ring (shrinking arena)
+    eliminated: bool,
+    /// Turn count when the player went out of the game (arena or no HP left)
+    out_turn: Option<u32>,
+}
+
+/// Most players a game can have
+pub const MAX_PLAYERS: usize = 4;
+
+/// Element of each player, by player id
+pub const PLAYER_ELEMENTS: [GameElements; MAX_PLAYERS] = [
+    GameElements::PlayerOne,
+    GameElements::PlayerTwo,
+    GameElements::PlayerThree,
+    GameElements::PlayerFour,
+];
+
 /// Useful for printing
 ///
 /// # Parameters
@@ -73,7 +141,7 @@ pub struct GameState {
 /// # Returns
 ///
 /// Char thar represent the game element
-fn transform_enum_elem_to_char(game_elem: &GameElements) -> char {
+pub fn transform_enum_elem_to_char(game_elem: &GameElements) -> char {
     match game_elem {
         GameElements::BonusATKBonusTile => '+',
         GameElements::ClearRoadBonusSpTile => 'c',
@@ -82,11 +150,32 @@ fn transform_enum_elem_to_char(game_elem: &GameElements) -> char {
         GameElements::Passable => 'o',
         GameElements::PlayerOne => '1',
         GameElements::PlayerTwo => '2',
+        GameElements::PlayerThree => '3',
+        GameElements::PlayerFour => '4',
+        GameElements::TeleportBonusSpTile => 't',
+        GameElements::WallBonusSpTile => 'w',
+        GameElements::SwapBonusSpTile => 's',
+        GameElements::DashBonusSpTile => 'd',
+        GameElements::TeleporterTile => '@',
+        GameElements::IceTile => '*',
+        GameElements::ConveyorUpTile => '^',
+        GameElements::ConveyorDownTile => 'v',
+        GameElements::ConveyorRightTile => '>',
+        GameElements::ConveyorLeftTile => '<',
+        GameElements::SkipTrapTile => '!',
+        GameElements::ATKTrapTile => '%',
+        GameElements::Void => '.',
     }
 }
 
+/// Whether a player can stand on the element: neither a wall nor the void
+pub fn is_passable_elem(game_elem: &GameElements) -> bool {
+    !matches!(game_elem, GameElements::NonPassable | GameElements::Void)
+}
+
 impl GameState {
-    /// Change state of the board game in x, y, and value
+    /// Change state of the board game in x, y, and value.
+    /// Covering a teleporter of a pair turns the other one passable.
     ///
     /// # Parameters
     ///
@@ -96,37 +185,178 @@ impl GameState {
     ///
     pub fn change_elem_on_coor(&mut self, x: usize, y: usize, val: GameElements) {
         self.map_representation[x][y] = val;
+        if val == GameElements::TeleporterTile {
+            return;
+        }
+        let Some(idx) = self.teleporter_pairs.iter().position(|pair| pair.contains(&(x, y))) else {
+            return;
+        };
+        let pair = self.teleporter_pairs.remove(idx);
+        for (xt, yt) in pair {
+            if self.map_representation[xt][yt] == GameElements::TeleporterTile {
+                self.map_representation[xt][yt] = GameElements::Passable;
+            }
+        }
     }
 
-    pub fn get_elem_on_coor(&self, x: usize, y: usize) -> &GameElements {
-        return &self.map_representation[x][y];
+    /// Other teleporter of the pair of a teleporter, None if it has no pair
+    pub fn get_paired_teleporter(&self, coor: (usize, usize)) -> Option<(usize, usize)> {
+        let pair = self.teleporter_pairs.iter().find(|pair| pair.contains(&coor))?;
+        pair.iter().copied().find(|teleporter| *teleporter != coor)
+    }
+
+    /// Pairs of teleporters still on the board
+    pub fn get_teleporter_pairs(&self) -> &[TeleporterPair] {
+        &self.teleporter_pairs
     }
 
-    /// get player special skill
     ///
-    /// # Parameters
-    /// player: id of player, possible choices: 0, 1
-    pub fn get_player_sp(&self, player: u8) -> &SpecialSkill {
-        if player == 0 {
-            &self.first_player_sp
+    /// Link teleporters by pairs, replacing the pairs of the state.
+    /// Only pairs of two teleporter tiles are kept.
+    ///
+    pub fn set_teleporter_pairs(&mut self, pairs: &[TeleporterPair]) {
+        self.teleporter_pairs = pairs
+            .iter()
+            .filter(|pair| {
+                pair[0] != pair[1]
+                    && pair
+                        .iter()
+                        .all(|(x, y)| self.map_representation[*x][*y] == GameElements::TeleporterTile)
+            })
+            .copied()
+            .collect();
+    }
+
+    pub fn get_elem_on_coor(&self, x: usize, y: usize) -> &GameElements {
+        &self.map_representation[x][y]
+    }
+
+    fn get_player(&self, player: u8) -> &PlayerState {
+        &self.players[player as usize]
+    }
+
+    fn get_player_mut(&mut self, player: u8) -> &mut PlayerState {
+        &mut self.players[player as usize]
+    }
+
+    /// Number of players, out of the game or not
+    pub fn get_player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Whether the player left the game: caught by the arena or knocked out
+    pub fn is_player_out(&self, player: u8) -> bool {
+        let state = self.get_player(player);
+        state.eliminated || (self.rules.combat.enabled && state.hp <= 0)
+    }
+
+    /// Players still in the game
+    pub fn get_active_players(&self) -> Vec<u8> {
+        (0..self.players.len() as u8)
+            .filter(|player| !self.is_player_out(*player))
+            .collect()
+    }
+
+    /// Number of teams, every player is its own team outside of team mode
+    pub fn get_team_count(&self) -> usize {
+        if self.rules.board.teams {
+            self.players.len().min(2)
         } else {
-            &self.second_player_sp
+            self.players.len()
         }
     }
 
-    pub fn get_player_position(&self) -> ((usize, usize), (usize, usize)) {
-        (self.first_player_pos, self.second_player_pos)
+    /// Team of the player. In team mode players 1 and 3 are team 0,
+    /// players 2 and 4 team 1, otherwise the team is the player id
+    pub fn get_team(&self, player: u8) -> u8 {
+        if self.rules.board.teams {
+            player % 2
+        } else {
+            player
+        }
+    }
+
+    /// Players of the team, out of the game or not
+    pub fn get_team_players(&self, team: u8) -> Vec<u8> {
+        (0..self.players.len() as u8)
+            .filter(|player| self.get_team(*player) == team)
+            .collect()
+    }
+
+    /// ATK of the team: the sum of the ATK of its players
+    pub fn get_team_atk(&self, team: u8) -> i16 {
+        self.get_team_players(team)
+            .into_iter()
+            .map(|player| self.get_player_atk(player))
+            .sum()
+    }
+
+    /// Players still in the game the player plays against, team-mates excluded
+    pub fn get_enemies(&self, player: u8) -> Vec<u8> {
+        let team = self.get_team(player);
+        self.get_active_players()
+            .into_iter()
+            .filter(|other| self.get_team(*other) != team)
+            .collect()
+    }
+
+    /// Player still in the game standing on the coordinate
+    pub fn get_player_on_coor(&self, coor: (usize, usize)) -> Option<u8> {
+        self.get_active_players()
+            .into_iter()
+            .find(|player| self.get_player_coor(*player) == coor)
+    }
+
+    /// Enemy of the player standing on the coordinate, team-mates are ignored
+    pub fn get_enemy_on_coor(&self, player: u8, coor: (usize, usize)) -> Option<u8> {
+        self.get_enemies(player)
+            .into_iter()
+            .find(|enemy| self.get_player_coor(*enemy) == coor)
+    }
+
+    /// get player special skills, oldest first.
+    /// Never holds `SpecialSkill::None`
+    ///
+    /// # Parameters
+    /// player: id of player
+    pub fn get_player_inventory(&self, player: u8) -> &[SpecialSkill] {
+        &self.get_player(player).inventory
+    }
+
+    /// get player special skill in a slot, None if the slot is empty
+    ///
+    /// # Parameters
+    /// player: id of player
+    /// slot: index in the inventory
+    pub fn get_player_sp(&self, player: u8, slot: usize) -> &SpecialSkill {
+        self.get_player_inventory(player)
+            .get(slot)
+            .unwrap_or(&SpecialSkill::None)
+    }
+
+    /// Positions of all the players, by player id
+    pub fn get_player_positions(&self) -> Vec<(usize, usize)> {
+        self.players.iter().map(|player| player.position).collect()
+    }
+
+    /// Position of the player
+    pub fn get_player_coor(&self, player: u8) -> (usize, usize) {
+        self.get_player(player).position
     }
 
     pub fn get_player_position_with_idx(&self, player: u8) -> (u8, u8) {
-        if player == 0 {
-            (self.first_player_pos.0 as u8, self.first_player_pos.1 as u8)
-        } else {
-            (
-                self.second_player_pos.0 as u8,
-                self.second_player_pos.1 as u8,
-            )
-        }
+        let (x, y) = self.get_player_coor(player);
+        (x as u8, y as u8)
+    }
+
+    /// Rules of the game
+    pub fn get_rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    /// Neighbourhood, distances and edges of the board
+    pub fn get_topology(&self) -> Topology {
+        Topology::new(&self.rules.board)
     }
 
     /// Getter of height_width
@@ -134,31 +364,147 @@ impl GameState {
         self.height_width
     }
 
-    pub fn get_player_atk(&self, is_player_one: bool) -> i16 {
-        if is_player_one {
-            self.first_player_atk
-        } else {
-            self.second_player_atk
+    pub fn get_player_atk(&self, player: u8) -> i16 {
+        self.get_player(player).atk
+    }
+
+    /// Pick up a special skill, following the pickup policy
+    /// of the rules when the inventory is full
+    pub fn add_player_sp(&mut self, player: u8, special: SpecialSkill) {
+        let size = self.rules.skills.inventory_size;
+        let policy = self.rules.skills.pickup;
+        if special == SpecialSkill::None || size == 0 {
+            return;
+        }
+        let inventory = &mut self.get_player_mut(player).inventory;
+        if inventory.len() < size {
+            inventory.push(special);
+            return;
+        }
+        match policy {
+            PickupPolicy::Replace => {
+                inventory.pop();
+                inventory.push(special);
+            }
+            PickupPolicy::Queue => {
+                inventory.remove(0);
+                inventory.push(special);
+            }
+            PickupPolicy::Refuse => (),
         }
     }
 
-    pub fn set_player_sp(&mut self, player: u8, special: SpecialSkill) {
-        if player == 0 {
-            self.first_player_sp = special;
-        } else {
-            self.second_player_sp = special;
+    /// Remove the special skill of a slot, nothing happens if the slot is empty
+    pub fn remove_player_sp(&mut self, player: u8, slot: usize) {
+        let inventory = &mut self.get_player_mut(player).inventory;
+        if slot < inventory.len() {
+            inventory.remove(slot);
         }
     }
 
+    /// HP of the player, only used in combat mode
+    pub fn get_player_hp(&self, player: u8) -> i16 {
+        self.get_player(player).hp
+    }
+
+    pub fn change_player_hp(&mut self, player: u8, point_inc: i16) {
+        let was_out = self.is_player_out(player);
+        self.get_player_mut(player).hp += point_inc;
+        self.record_out_turn(player, was_out);
+    }
+
+    /// Remember the turn the player went out of the game
+    fn record_out_turn(&mut self, player: u8, was_out: bool) {
+        let turn_count = self.turn_count;
+        let is_out = self.is_player_out(player);
+        let state = self.get_player_mut(player);
+        match (was_out, is_out) {
+            (false, true) => state.out_turn = Some(turn_count),
+            (_, false) => state.out_turn = None,
+            _ => (),
+        }
+    }
+
+    /// Turn count when the player went out of the game, None while it is in
+    pub fn get_player_out_turn(&self, player: u8) -> Option<u32> {
+        self.get_player(player).out_turn
+    }
+
+    /// Number of enemy actions before the player can act again (traps)
+    pub fn get_player_wait(&self, player: u8) -> u8 {
+        self.get_player(player).wait
+    }
+
+    pub fn set_player_wait(&mut self, player: u8, wait: u8) {
+        self.get_player_mut(player).wait = wait;
+    }
+
+    /// Whether the player was caught by a collapsing ring (shrinking arena)
+    pub fn is_player_eliminated(&self, player: u8) -> bool {
+        self.get_player(player).eliminated
+    }
+
+    pub fn eliminate_player(&mut self, player: u8) {
+        let was_out = self.is_player_out(player);
+        self.get_player_mut(player).eliminated = true;
+        self.record_out_turn(player, was_out);
+    }
+
+    /// Number of actions played since the start
+    pub fn get_turn_count(&self) -> u32 {
+        self.turn_count
+    }
+
+    /// Seed of the map, also used by the tile respawn
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Generator of the map, None for a hand-authored map
+    pub fn get_map_randomness(&self) -> Option<MapRandom> {
+        self.map_randomness
+    }
+
+    pub fn increment_turn_count(&mut self) {
+        self.turn_count += 1;
+    }
+
+    pub fn set_turn_count(&mut self, turn_count: u32) {
+        self.turn_count = turn_count;
+    }
+
     pub fn change_player_atk(&mut self, player: u8, point_inc: i16) {
-        if player == 0 {
-            let new = self.first_player_atk + point_inc;
-            self.first_player_atk = new;
-        } else {
-            let new = self.second_player_atk + point_inc;
-            self.second_player_atk = new;
-        };
+        self.get_player_mut(player).atk += point_inc;
     }
+
+    /// Compact string of the whole state, usable as a key
+    /// (map column by column, turn, then position, ATK, HP, wait,
+    /// elimination and special skills of each player)
+    pub fn get_state_key(&self) -> String {
+        let map: String = self
+            .map_representation
+            .iter()
+            .flatten()
+            .map(transform_enum_elem_to_char)
+            .collect();
+        let players: Vec<String> = self
+            .players
+            .iter()
+            .map(|player| {
+                format!(
+                    "{:?},{},{},{},{},{:?}",
+                    player.position,
+                    player.atk,
+                    player.hp,
+                    player.wait,
+                    player.eliminated,
+                    player.inventory
+                )
+            })
+            .collect();
+        format!("{}|{}|{}", map, self.turn_count, players.join("|"))
+    }
+
     /// Getter of map
     pub fn get_map_representation(&self) -> &Vec<Vec<GameElements>> {
         &self.map_representation
@@ -169,19 +515,25 @@ impl GameState {
     /// so start looping from the first index
     /// We use first index
     pub fn print_map(&self) {
-        let ((x1, y1), (x2, y2)) = self.get_player_position();
+        let active_players = self.get_active_players();
+        let topology = self.get_topology();
         for y in 0..self.height_width {
-            let mut collect_str: String = "".to_string();
+            let mut collect_str: String = topology.get_row_indent(y);
             for x in 0..self.height_width {
-                // COLLIDE!
-                if x1 == x && x2 == x && y1 == y && y2 == y {
+                let on_tile: Vec<u8> = active_players
+                    .iter()
+                    .copied()
+                    .filter(|player| self.get_player_coor(*player) == (x, y))
+                    .collect();
+                // COLLIDE! Team-mates only pass through each other
+                let collide = on_tile
+                    .iter()
+                    .any(|player| self.get_team(*player) != self.get_team(on_tile[0]));
+                if collide {
                     collect_str.push('V');
                     collect_str.push(' ');
-                } else if x1 == x && y1 == y {
-                    collect_str.push('1');
-                    collect_str.push(' ');
-                } else if x2 == x && y2 == y {
-                    collect_str.push('2');
+                } else if let Some(player) = on_tile.first() {
+                    collect_str.push(transform_enum_elem_to_char(&PLAYER_ELEMENTS[*player as usize]));
                     collect_str.push(' ');
                 } else {
                     collect_str.push(transform_enum_elem_to_char(&self.map_representation[x][y]));
@@ -194,27 +546,59 @@ impl GameState {
 
     /// print state pretty
     pub fn print_pretty_state(&self) {
-        println!(
-            "ATK P1 = {} \t ATK P2 = {}",
-            self.first_player_atk, self.second_player_atk
-        );
-        println!(
-            "SP SKILL P1 = {:#?} \t SP SKILL P2 = {:#?}",
-            &self.first_player_sp, &self.second_player_sp
-        );
+        let line = |name: &str, value: &dyn Fn(&PlayerState) -> String| {
+            let values: Vec<String> = self
+                .players
+                .iter()
+                .enumerate()
+                .map(|(idx, player)| format!("{} P{} = {}", name, idx + 1, value(player)))
+                .collect();
+            println!("{}", values.join(" \t "));
+        };
+        line("ATK", &|player| player.atk.to_string());
+        if self.rules.board.teams {
+            let teams: Vec<String> = (0..self.get_team_count() as u8)
+                .map(|team| format!("TEAM ATK T{} = {}", team + 1, self.get_team_atk(team)))
+                .collect();
+            println!("{}", teams.join(" \t "));
+        }
+        if self.rules.combat.enabled {
+            line("HP", &|player| player.hp.to_string());
+        }
+        line("SP SKILL", &|player| format!("{:?}", player.inventory));
+        let out: Vec<String> = (0..self.players.len() as u8)
+            .filter(|player| self.is_player_out(*player))
+            .map(|player| format!("P{}", player + 1))
+            .collect();
+        if !out.is_empty() {
+            println!("OUT: {}", out.join(", "));
+        }
+        if let Some(board) = self.get_topology().get_description() {
+            println!("BOARD: {}", board);
+        }
+        if let Some((turn, ring)) = get_next_collapse(self) {
+            println!(
+                "ARENA: ring {} collapses after turn {} (turn {} now)",
+                ring, turn, self.turn_count
+            );
+        }
+        if let Some(spawn) = get_next_spawn(self) {
+            let tiles: Vec<String> = spawn
+                .tiles
+                .iter()
+                .map(|((x, y), tile)| {
+                    format!("{} at {},{}", transform_enum_elem_to_char(tile), x, y)
+                })
+                .collect();
+            println!("RESPAWN: after turn {}: {}", spawn.turn, tiles.join(", "));
+        }
         self.print_map();
         println!("---")
     }
 
     /// Change player position to new pos_x and new_pos_y
     pub fn change_player_pos(&mut self, player: u8, new_pos_x: usize, new_pos_y: usize) {
-        let mut player = if player == 0 {
-            &mut self.first_player_pos
-        } else {
-            &mut self.second_player_pos
-        };
-        player.0 = new_pos_x;
-        player.1 = new_pos_y;
+        self.get_player_mut(player).position = (new_pos_x, new_pos_y);
     }
 
     ///
@@ -224,71 +608,292 @@ impl GameState {
     /// height_width: Height and Width of the area
     ///
     pub fn new(height_width: usize, map_randomness: MapRandom) -> GameState {
-        let map_repr = create_map_representation(height_width, map_randomness);
+        GameState::new_with_rules(
+            &RuleSet::with_board_size(height_width),
+            map_randomness,
+            rand::random(),
+        )
+    }
+
+    ///
+    /// Instantiate a GameState Struct with a reproducible map
+    ///
+    /// # Parameters
+    /// height_width: Height and Width of the area
+    /// seed: Same seed gives the same map
+    ///
+    pub fn new_with_seed(height_width: usize, map_randomness: MapRandom, seed: u64) -> GameState {
+        GameState::new_with_rules(&RuleSet::with_board_size(height_width), map_randomness, seed)
+    }
+
+    ///
+    /// Instantiate a GameState Struct following a rule set
+    ///
+    /// # Parameters
+    /// rules: Rules of the game, the board size comes from there
+    /// seed: Same seed gives the same map
+    ///
+    pub fn new_with_rules(rules: &RuleSet, map_randomness: MapRandom, seed: u64) -> GameState {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let height_width = rules.board.size;
+        let player_count = rules.board.players.clamp(2, MAX_PLAYERS);
+        let (map_representation, starts) = create_map_representation(
+            height_width,
+            player_count,
+            rules.board.wrap,
+            map_randomness,
+            &rules.generation,
+            &mut rng,
+        );
+        GameState::from_map(rules, map_representation, starts, seed, Some(map_randomness))
+    }
+
+    ///
+    /// Instantiate a GameState Struct on a hand-authored map
+    ///
+    /// # Parameters
+    /// rules: Rules of the game, the board size comes from the map
+    /// map: Tiles and start positions
+    /// seed: Seed of the random events (respawns)
+    ///
+    /// # Returns
+    /// The state, or a message if the map has not enough start positions for the players
+    ///
+    pub fn new_with_map(rules: &RuleSet, map: &GameMap, seed: u64) -> Result<GameState, String> {
+        let player_count = rules.board.players.clamp(2, MAX_PLAYERS);
+        if map.starts.len() < player_count {
+            return Err(format!(
+                "Map {} has {} start positions, {} players need one each",
+                map.name,
+                map.starts.len(),
+                player_count
+            ));
+        }
+        let mut rules = rules.clone();
+        rules.board.size = map.get_size();
+        let starts = map.starts[..player_count].to_vec();
+        Ok(GameState::from_map(&rules, map.tiles.clone(), starts, seed, None))
+    }
+
+    ///
+    /// State at the start of the game, one player on each start position.
+    /// Teleporters are paired in column order: 1st with 2nd, 3rd with 4th...
+    ///
+    fn from_map(
+        rules: &RuleSet,
+        map_representation: Vec<Vec<GameElements>>,
+        starts: Vec<(usize, usize)>,
+        seed: u64,
+        map_randomness: Option<MapRandom>,
+    ) -> GameState {
+        let height_width = map_representation.len();
+        let players = starts
+            .into_iter()
+            .map(|position| PlayerState {
+                position,
+                inventory: Vec::new(),
+                atk: 0,
+                hp: rules.combat.hp,
+                wait: 0,
+                eliminated: false,
+                out_turn: None,
+            })
+            .collect();
+        let teleporters: Vec<(usize, usize)> = (0..height_width)
+            .flat_map(|x| (0..height_width).map(move |y| (x, y)))
+            .filter(|(x, y)| map_representation[*x][*y] == GameElements::TeleporterTile)
+            .collect();
+        let teleporter_pairs = teleporters
+            .chunks_exact(2)
+            .map(|pair| [pair[0], pair[1]])
+            .collect();
         GameState {
-            height_width: height_width,
-            map_representation: map_repr.0,
-            first_player_pos: map_repr.1,
-            second_player_pos: map_repr.2,
-            first_player_sp: SpecialSkill::None,
-            second_player_sp: SpecialSkill::None,
-            first_player_atk: 0,
-            second_player_atk: 0,
+            rules: Arc::new(rules.clone()),
+            height_width,
+            map_representation,
+            players,
+            turn_count: 0,
+            seed,
+            teleporter_pairs,
+            map_randomness,
         }
     }
 }
 
 ///
-/// Create map representation of the game itself
-/// TODO: add more **Compact MAP**
+/// Start coordinates of the players.
+/// Two players start in the middle of the top and bottom rows,
+/// more players are spread evenly along the border, clockwise from the first one.
+/// On a torus the border touches the opposite one, the players are spread
+/// along the diagonal from the first one instead. On a board smaller than the
+/// number of players a taken tile is replaced by the next free one of its row.
+///
+pub fn get_start_positions(
+    height_width: usize,
+    player_count: usize,
+    wrap: bool,
+) -> Vec<(usize, usize)> {
+    let player_mid_location = height_width.div_ceil(2);
+    let player_one_coor = (player_mid_location.saturating_sub(1), 0); // x, y
+    if wrap {
+        let mut starts: Vec<(usize, usize)> = Vec::with_capacity(player_count);
+        for idx in 0..player_count {
+            let shift = idx * height_width / player_count;
+            let x = player_one_coor.0 + shift;
+            // the board has at least 4 tiles, always enough for 4 players
+            let start = (0..height_width * height_width)
+                .map(|step| {
+                    (
+                        (x + step) % height_width,
+                        (shift + step / height_width) % height_width,
+                    )
+                })
+                .find(|coor| !starts.contains(coor))
+                .expect("The board has a free tile for every player");
+            starts.push(start);
+        }
+        return starts;
+    }
+    if player_count <= 2 {
+        let player_two_coor = (player_mid_location - 1, height_width - 1);
+        return vec![player_one_coor, player_two_coor];
+    }
+
+    // border clockwise from the top left corner
+    let last = height_width - 1;
+    let mut border: Vec<(usize, usize)> = (0..last).map(|x| (x, 0)).collect();
+    border.extend((0..last).map(|y| (last, y)));
+    border.extend((1..=last).rev().map(|x| (x, last)));
+    border.extend((1..=last).rev().map(|y| (0, y)));
+    let first = border
+        .iter()
+        .position(|coor| *coor == player_one_coor)
+        .unwrap_or(0);
+    (0..player_count)
+        .map(|idx| border[(first + idx * border.len() / player_count) % border.len()])
+        .collect()
+}
+
+///
+/// Create map representation of the game itself.
+/// `Caves`, `Maze` and `Rooms` place the walls first (see the Map Generation Module),
+/// the open tiles are drawn without non passable tiles.
 ///
 /// # Returns
 /// Return the vector creation
 ///
 fn create_map_representation(
     height_width: usize,
+    player_count: usize,
+    wrap: bool,
     map_randomness: MapRandom,
-) -> (Vec<Vec<GameElements>>, (usize, usize), (usize, usize)) {
+    generation: &GenerationRules,
+    rng: &mut impl Rng,
+) -> (Vec<Vec<GameElements>>, Vec<(usize, usize)>) {
     // Placeholder
     let mut vec = vec![vec![GameElements::Passable; height_width]; height_width];
-    let x_mid = f32::from(i8::try_from(height_width).unwrap()) / 2.0;
-    let player_mid_location = x_mid.ceil() as i8 as usize;
-    let player_one_coor = (player_mid_location - 1, 0); // x, y
-    let player_two_coor = (player_mid_location - 1, height_width - 1);
+    let starts = get_start_positions(height_width, player_count, wrap);
 
     // creater randomness on the map based on the MapRandomChoice
     if map_randomness == MapRandom::FullyRandom {
         // Fully Random : Just randomm!!
-        // end of game element of PASSABLE chance
-        let end_ge = GameElements::COUNT - 3;
-        for i in 0..vec.len() {
-            for j in 0..vec[i].len() {
+        for (i, row) in vec.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
                 // if position is the player, SKIP!
-                if (i == player_one_coor.0 && j == player_one_coor.1)
-                    || (i == player_two_coor.0 && j == player_two_coor.1)
-                {
+                if starts.contains(&(i, j)) {
                     continue;
                 }
-                let mut rng = rand::thread_rng();
-                let random_num = rng.gen_range(0..10);
-                // let choosen_elem: GameElements;
-                let choosen_elem = if random_num <= end_ge {
-                    GameElements::Passable
-                } else if random_num > end_ge + 1 && random_num < end_ge + 3 {
-                    GameElements::BonusATKBonusTile
-                } else if random_num == end_ge + 3 {
-                    GameElements::ClearRoadBonusSpTile
-                } else if random_num == end_ge + 4 {
-                    GameElements::MinusATKBonusSpTile
-                } else {
+                *cell = draw_random_tile(generation, rng);
+            }
+        }
+        place_teleporter_pairs(&mut vec, generation.teleporter_pairs, &starts, rng);
+    } else {
+        let walls = generate_walls(height_width, map_randomness, generation, &starts, rng);
+        let open_tiles = GenerationRules {
+            non_passable: 0,
+            ..generation.clone()
+        };
+        for (i, row) in vec.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                if starts.contains(&(i, j)) {
+                    continue;
+                }
+                *cell = if walls[i][j] {
                     GameElements::NonPassable
+                } else {
+                    draw_random_tile(&open_tiles, rng)
                 };
-                vec[i][j] = choosen_elem;
             }
         }
+        place_teleporter_pairs(&mut vec, generation.teleporter_pairs, &starts, rng);
+    }
+    (vec, starts)
+}
 
-        // Loop all of them and put it randomly!
-        // Make passable has higher chance than the others
+///
+/// Turn random passable tiles into teleporters, two per pair.
+/// Start positions are left out.
+///
+fn place_teleporter_pairs(
+    map: &mut [Vec<GameElements>],
+    pairs: u32,
+    starts: &[(usize, usize)],
+    rng: &mut impl Rng,
+) {
+    for _ in 0..pairs {
+        let free: Vec<(usize, usize)> = (0..map.len())
+            .flat_map(|x| (0..map.len()).map(move |y| (x, y)))
+            .filter(|coor| map[coor.0][coor.1] == GameElements::Passable && !starts.contains(coor))
+            .collect();
+        if free.len() < 2 {
+            return;
+        }
+        for (x, y) in free.choose_multiple(rng, 2) {
+            map[*x][*y] = GameElements::TeleporterTile;
+        }
+    }
+}
+
+///
+/// Draw a tile following the generation weights of the rules
+/// (passable has the higher chance by default)
+///
+pub fn draw_random_tile(generation: &GenerationRules, rng: &mut impl Rng) -> GameElements {
+    let weighted_tiles = [
+        (generation.passable, GameElements::Passable),
+        (generation.non_passable, GameElements::NonPassable),
+        (generation.bonus_atk, GameElements::BonusATKBonusTile),
+        (generation.clear_road, GameElements::ClearRoadBonusSpTile),
+        (generation.minus_atk, GameElements::MinusATKBonusSpTile),
+        (generation.teleport, GameElements::TeleportBonusSpTile),
+        (generation.wall, GameElements::WallBonusSpTile),
+        (generation.swap, GameElements::SwapBonusSpTile),
+        (generation.dash, GameElements::DashBonusSpTile),
+        (generation.ice, GameElements::IceTile),
+        (generation.conveyor, GameElements::ConveyorUpTile),
+        (generation.skip_trap, GameElements::SkipTrapTile),
+        (generation.atk_trap, GameElements::ATKTrapTile),
+    ];
+    let total: u32 = weighted_tiles.iter().map(|(weight, _)| weight).sum();
+    if total == 0 {
+        return GameElements::Passable;
+    }
+    let mut random_num = rng.gen_range(0..total);
+    for (weight, tile) in weighted_tiles {
+        if random_num < weight && tile == GameElements::ConveyorUpTile {
+            // any direction
+            let conveyors = [
+                GameElements::ConveyorUpTile,
+                GameElements::ConveyorDownTile,
+                GameElements::ConveyorRightTile,
+                GameElements::ConveyorLeftTile,
+            ];
+            return conveyors[rng.gen_range(0..conveyors.len())];
+        }
+        if random_num < weight {
+            return tile;
+        }
+        random_num -= weight;
     }
-    (vec, player_one_coor, player_two_coor)
+    GameElements::Passable
 }
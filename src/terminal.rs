@@ -1,58 +1,345 @@
-use crate::action::get_legal_action;
+use serde::{Deserialize, Serialize};
+
+use crate::action::{get_available_action, get_legal_action};
+use crate::search::evaluation::get_distance_map;
 use crate::state_repr::GameState;
 
 
+/// Why the game ended
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EndReason {
+    /// No move left for any player (stuck / trapped)
+    BothTrapped,
+    /// Two enemies collided (if `collision_ends_game` in the rules)
+    Collision,
+    /// Every team but one has no HP left (combat mode)
+    KnockOut,
+    /// Players were caught by a collapsing ring until one team is left (shrinking arena)
+    ArenaCollapse,
+    /// `max_turns` turns were played
+    TurnLimit,
+    /// A team reached `target_atk`
+    TargetATK,
+    /// Only one team can still move (`last_mover_wins`)
+    LastMover,
+    /// The teams can't reach each other anymore (`territory_majority`)
+    TerritoryMajority,
+}
+
+impl EndReason {
+    /// Sentence for the players
+    pub fn get_description(&self) -> &'static str {
+        match self {
+            EndReason::BothTrapped => "every player is trapped, ATK decides",
+            EndReason::Collision => "two enemies collided, ATK decides",
+            EndReason::KnockOut => "only one team is still standing",
+            EndReason::ArenaCollapse => "the players were caught by the collapsing arena",
+            EndReason::TurnLimit => "turn limit reached, ATK decides",
+            EndReason::TargetATK => "a team reached the target ATK",
+            EndReason::LastMover => "only one team can still move",
+            EndReason::TerritoryMajority => "the players are separated, the bigger territory wins",
+        }
+    }
+}
+
+
 /// Check if the game ended
 /// 
-/// # Criteria
+/// # Parameters
 /// 
-/// This is the criteria how the game ended
-/// 1. No move left for both of players (stuck / trapped)
-/// 2. Player 1 and Player 2 collided
+/// * state: The current state
+/// 
+pub fn is_terminal_state(state: &GameState) -> bool {
+    get_end_reason(state).is_some()
+}
+
+
+/// Why the game ended, None if it goes on.
+/// Reasons are checked in the order of `EndReason`, the optional ones
+/// only when the rules turn them on.
 /// 
 /// # Parameters
 /// 
 /// * state: The current state
 /// 
-pub fn is_terminal_state(state: &GameState) -> bool {
-    let p1_actions = get_legal_action(&state, 0);
-    let p2_actions = get_legal_action(&state, 1);
+pub fn get_end_reason(state: &GameState) -> Option<EndReason> {
+    let rules = state.get_rules();
+    let active_players = state.get_active_players();
+    let active_teams = get_teams_of(state, active_players.iter().copied());
+    let player_count = state.get_player_count() as u8;
 
-    let ((x1,y1), (x2,y2)) = state.get_player_position();
-    
-    // condition 1
-    if p1_actions.len() == 0 && p2_actions.len() == 0 {
-        return true;
+    // the game goes on as long as two teams are in
+    if active_teams.len() <= 1 {
+        if (0..player_count).any(|player| state.is_player_eliminated(player)) {
+            return Some(EndReason::ArenaCollapse);
+        }
+        return Some(EndReason::KnockOut);
+    }
+    let target_reached =
+        |target: i16| active_teams.iter().any(|team| state.get_team_atk(*team) >= target);
+    if rules.end.target_atk.is_some_and(target_reached) {
+        return Some(EndReason::TargetATK);
+    }
+    // team-mates pass through each other
+    let collided = active_players.iter().any(|player| {
+        state.get_enemy_on_coor(*player, state.get_player_coor(*player)).is_some()
+    });
+    if rules.end.collision_ends_game && collided {
+        return Some(EndReason::Collision);
     }
-    
-    if x1 == x2 && y1 == y2 {
-        return true;
+    if active_players.iter().all(|player| get_legal_action(state, *player).is_empty()) {
+        return Some(EndReason::BothTrapped);
     }
+    // a player waiting on a trap is not stuck
+    let movers = active_players
+        .iter()
+        .copied()
+        .filter(|player| !get_available_action(state, *player).is_empty());
+    if rules.end.last_mover_wins && get_teams_of(state, movers).len() == 1 {
+        return Some(EndReason::LastMover);
+    }
+    if rules.end.territory_majority && get_territories(state).is_some() {
+        return Some(EndReason::TerritoryMajority);
+    }
+    if rules.end.max_turns.is_some_and(|max_turns| state.get_turn_count() >= max_turns) {
+        return Some(EndReason::TurnLimit);
+    }
+    None
+}
+
+
+/// Teams of the players, without duplicates
+fn get_teams_of(state: &GameState, players: impl Iterator<Item = u8>) -> Vec<u8> {
+    let mut teams = Vec::new();
+    for player in players {
+        let team = state.get_team(player);
+        if !teams.contains(&team) {
+            teams.push(team);
+        }
+    }
+    teams
+}
 
-    return false;
+
+/// Number of tiles each player can still reach, by player id, None while
+/// two enemies in the game can reach each other.
+/// Players out of the game have no territory, a tile reached by team-mates
+/// only counts for the first of them.
+pub fn get_territories(state: &GameState) -> Option<Vec<usize>> {
+    let mut territories = vec![0; state.get_player_count()];
+    let active_players = state.get_active_players();
+    let mut counted: Vec<Vec<Vec<Option<u16>>>> = Vec::new();
+    for (idx, player) in active_players.iter().enumerate() {
+        let distances = get_distance_map(state, state.get_player_coor(*player));
+        let reaches_enemy = active_players[idx + 1..].iter().any(|other| {
+            let (x, y) = state.get_player_coor(*other);
+            state.get_team(*other) != state.get_team(*player) && distances[x][y].is_some()
+        });
+        if reaches_enemy {
+            return None;
+        }
+        let (x, y) = state.get_player_coor(*player);
+        let mate_counted = active_players[..idx].iter().zip(counted.iter()).any(|(mate, dist)| {
+            state.get_team(*mate) == state.get_team(*player) && dist[x][y].is_some()
+        });
+        if !mate_counted {
+            territories[*player as usize] =
+                distances.iter().flatten().filter(|d| d.is_some()).count();
+        }
+        counted.push(distances);
+    }
+    Some(territories)
+}
+
+
+/// Result of a finished game, for UIs, replays and stats.
+/// Outside of team mode every player is its own team, the team id is the player id.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameOutcome {
+    /// Team that won alone, None on a draw
+    pub winner: Option<u8>,
+    pub reason: EndReason,
+    /// Team of each player
+    pub teams: Vec<u8>,
+    /// Final place of each team, 1 is the best, tied teams share the place
+    pub team_ranking: Vec<u8>,
+    /// Final place of each player, the place of its team
+    pub ranking: Vec<u8>,
+    /// ATK of each player
+    pub final_atk: Vec<i16>,
+    /// HP of each player (combat mode)
+    pub final_hp: Vec<i16>,
+    /// Number of actions played
+    pub turn_count: u32,
+}
+
+impl GameOutcome {
+    /// 1 win, -1 lose, 0 draw (first place shared), the result of the team of the player
+    pub fn get_result_for_player(&self, player: u8) -> i16 {
+        match self.winner {
+            Some(winner) if winner == self.teams[player as usize] => 1,
+            None if self.ranking[player as usize] == 1 => 0,
+            _ => -1,
+        }
+    }
 }
 
 
-/// Decide winner score here
+/// Outcome of the game, None if it is not over
 /// 
 /// # Parameters
 /// 
 /// * state: The state
-/// * is_player_one: If the score that want to be checked is player one, set it to true
+/// 
+pub fn get_game_outcome(state: &GameState) -> Option<GameOutcome> {
+    let reason = get_end_reason(state)?;
+    let team_ranking = get_team_ranking(state, reason);
+    let first: Vec<usize> = (0..team_ranking.len()).filter(|idx| team_ranking[*idx] == 1).collect();
+    let winner = if first.len() == 1 { Some(first[0] as u8) } else { None };
+    let player_count = state.get_player_count() as u8;
+    Some(GameOutcome {
+        winner,
+        reason,
+        teams: (0..player_count).map(|player| state.get_team(player)).collect(),
+        ranking: get_ranking(state, reason),
+        team_ranking,
+        final_atk: (0..player_count).map(|player| state.get_player_atk(player)).collect(),
+        final_hp: (0..player_count).map(|player| state.get_player_hp(player)).collect(),
+        turn_count: state.get_turn_count(),
+    })
+}
+
+
+/// Final place of each team, 1 is the best.
+/// Teams are compared on what the end reason is about:
+/// 
+/// * ArenaCollapse: teams caught by the arena lose
+/// * LastMover: the teams that can't move lose
+/// * TerritoryMajority: the bigger territory wins, then HP / ATK
+/// * TargetATK: the teams that reached the target win, then HP / ATK
+/// * Otherwise: in combat mode the team with more HP wins, ATK breaks ties
+/// 
+/// Team-mates add up their HP, ATK and territory.
+/// Teams already out of the game are always behind the others,
+/// the later a team went out the better its place.
+/// 
+/// # Returns
+/// 
+/// The place of each team by id, tied teams share the best place
+/// 
+pub fn get_team_ranking(state: &GameState, reason: EndReason) -> Vec<u8> {
+    let territories = match reason {
+        EndReason::TerritoryMajority => get_territories(state).unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    let combat = state.get_rules().combat.enabled;
+    let keys: Vec<Vec<i32>> = (0..state.get_team_count() as u8)
+        .map(|team| {
+            let players = state.get_team_players(team);
+            let sum = |value: &dyn Fn(u8) -> i32| players.iter().map(|p| value(*p)).sum::<i32>();
+            let in_game = players.iter().any(|player| !state.is_player_out(*player));
+            // a team is out with its last player
+            let out_turn = players
+                .iter()
+                .map(|player| state.get_player_out_turn(*player).map_or(i32::MAX, |turn| turn as i32))
+                .max()
+                .unwrap_or(i32::MAX);
+            let mut key = vec![i32::from(in_game), out_turn];
+            match reason {
+                EndReason::ArenaCollapse => return key,
+                EndReason::LastMover => {
+                    let can_move = sum(&|p| i32::from(!get_available_action(state, p).is_empty()));
+                    key.push(i32::from(can_move > 0));
+                    return key;
+                }
+                EndReason::TerritoryMajority => {
+                    key.push(sum(&|p| territories.get(p as usize).copied().unwrap_or(0) as i32));
+                }
+                EndReason::TargetATK => {
+                    let target = state.get_rules().end.target_atk.unwrap_or(i16::MAX);
+                    key.push(i32::from(state.get_team_atk(team) >= target));
+                }
+                _ => (),
+            }
+            if combat {
+                key.push(sum(&|p| i32::from(state.get_player_hp(p))));
+            }
+            key.push(i32::from(state.get_team_atk(team)));
+            key
+        })
+        .collect();
+    keys.iter()
+        .map(|key| 1 + keys.iter().filter(|other| *other > key).count() as u8)
+        .collect()
+}
+
+
+/// Final place of each player by id: the place of its team
+pub fn get_ranking(state: &GameState, reason: EndReason) -> Vec<u8> {
+    let team_ranking = get_team_ranking(state, reason);
+    (0..state.get_player_count() as u8)
+        .map(|player| team_ranking[state.get_team(player) as usize])
+        .collect()
+}
+
+
+/// Points of the player at the end of the game, following the scoring of the rules.
+/// Team-mates get the points of their team.
+/// With more than 2 teams and a `ranking` scoring, the points of the place
+/// are given, tied teams share the points of their places.
+/// 
+/// # Parameters
+/// 
+/// * state: The state
+/// * player: id of the player
+/// 
+pub fn get_score_for_player(state: &GameState, player: u8) -> i16 {
+    let scoring = &state.get_rules().scoring;
+    if state.get_team_count() > 2 && !scoring.ranking.is_empty() {
+        if let Some(reason) = get_end_reason(state) {
+            let ranking = get_team_ranking(state, reason);
+            let place = ranking[state.get_team(player) as usize];
+            let tied = ranking.iter().filter(|other| **other == place).count();
+            let start = usize::from(place) - 1;
+            let points: i32 = (start..start + tied)
+                .map(|idx| i32::from(scoring.ranking.get(idx).copied().unwrap_or(0)))
+                .sum();
+            return (points / tied as i32) as i16;
+        }
+    }
+    match get_result_for_player(state, player) {
+        1 => scoring.win,
+        -1 => scoring.lose,
+        _ => scoring.draw,
+    }
+}
+
+
+/// Who won, whatever the scoring of the rules is.
+/// Used by the bots and the training code.
+/// 
+/// # Parameters
+/// 
+/// * state: The state
+/// * player: id of the player
+/// 
+/// Follows `get_ranking`.
 /// 
 /// # Returns
 /// 
-/// Return the state of the winner!
+/// 1 win (first place alone), -1 lose, 0 draw (first place shared),
+/// the result of the team of the player
 /// 
-pub fn get_point_for_player_on_terminal(state: &GameState, is_player_one: bool) -> i16 {
-    println!("{:#?}", state);
-    if state.get_player_atk(is_player_one) > state.get_player_atk(!is_player_one) {
-        // WIN
-        100
+pub fn get_result_for_player(state: &GameState, player: u8) -> i16 {
+    let Some(reason) = get_end_reason(state) else {
+        return 0;
+    };
+    let ranking = get_team_ranking(state, reason);
+    if ranking[state.get_team(player) as usize] != 1 {
+        -1
     }
-    else if state.get_player_atk(is_player_one) < state.get_player_atk(!is_player_one) {
-        // LOSE
-        -100
+    else if ranking.iter().filter(|place| **place == 1).count() == 1 {
+        1
     }
-    else { 0 } // DRAW
-}
\ No newline at end of file
+    else { 0 }
+}
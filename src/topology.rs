@@ -0,0 +1,182 @@
+//! Topology Module
+//!
+//! Which tiles are next to each other, how far apart they are and
+//! what happens at the edges of the board.
+//!
+//! * `square`: 4 neighbours (up, down, left, right)
+//! * `octile`: 8 neighbours, the diagonals too
+//! * `hex`: 6 neighbours. The board is a rhombus, every row is shifted
+//!   half a tile to the right of the row above (axial coordinates)
+//!
+//! With `wrap` the board is a torus: leaving by an edge comes back by the opposite one.
+//! Every movement, aim and distance of the game goes through here.
+//!
+use crate::action::Direction;
+use crate::rules::{BoardRules, GridKind};
+use crate::utils::is_not_out_of_bound;
+
+const SQUARE_DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Right,
+    Direction::Left,
+];
+
+const OCTILE_DIRECTIONS: [Direction; 8] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Right,
+    Direction::Left,
+    Direction::UpRight,
+    Direction::UpLeft,
+    Direction::DownRight,
+    Direction::DownLeft,
+];
+
+const HEX_DIRECTIONS: [Direction; 6] = [
+    Direction::Right,
+    Direction::Left,
+    Direction::UpRight,
+    Direction::UpLeft,
+    Direction::DownRight,
+    Direction::DownLeft,
+];
+
+/// Shape of the board
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Topology {
+    pub grid: GridKind,
+    pub wrap: bool,
+    pub height_width: usize,
+}
+
+impl Topology {
+    pub fn new(board: &BoardRules) -> Topology {
+        Topology {
+            grid: board.grid,
+            wrap: board.wrap,
+            height_width: board.size,
+        }
+    }
+
+    /// Directions of the grid, in `Direction` order
+    pub fn get_directions(&self) -> &'static [Direction] {
+        match self.grid {
+            GridKind::Square => &SQUARE_DIRECTIONS,
+            GridKind::Octile => &OCTILE_DIRECTIONS,
+            GridKind::Hex => &HEX_DIRECTIONS,
+        }
+    }
+
+    /// Offset (x, y) of one step in the direction, None if the grid has no such direction
+    pub fn get_offset(&self, direction: Direction) -> Option<(i8, i8)> {
+        if !self.get_directions().contains(&direction) {
+            return None;
+        }
+        let offset = match (self.grid, direction) {
+            (_, Direction::Up) => (0, -1),
+            (_, Direction::Down) => (0, 1),
+            (_, Direction::Right) => (1, 0),
+            (_, Direction::Left) => (-1, 0),
+            // rows shifted to the right going down
+            (GridKind::Hex, Direction::UpRight) => (1, -1),
+            (GridKind::Hex, Direction::UpLeft) => (0, -1),
+            (GridKind::Hex, Direction::DownRight) => (0, 1),
+            (GridKind::Hex, Direction::DownLeft) => (-1, 1),
+            (_, Direction::UpRight) => (1, -1),
+            (_, Direction::UpLeft) => (-1, -1),
+            (_, Direction::DownRight) => (1, 1),
+            (_, Direction::DownLeft) => (-1, 1),
+        };
+        Some(offset)
+    }
+
+    ///
+    /// Tile one step away in the direction
+    ///
+    /// # Parameters
+    ///
+    /// * coor: x, y starting coordinate
+    /// * direction: Direction of the step
+    ///
+    /// # Returns
+    ///
+    /// None if the step leaves the board (never on a torus) or the grid has no such direction
+    pub fn get_neighbour(&self, coor: (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        let (dx, dy) = self.get_offset(direction)?;
+        let size = self.height_width as i16;
+        let x = coor.0 as i16 + dx as i16;
+        let y = coor.1 as i16 + dy as i16;
+        if self.wrap {
+            return Some((x.rem_euclid(size) as usize, y.rem_euclid(size) as usize));
+        }
+        if is_not_out_of_bound(x as i8, y as i8, size as i8) {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    /// Every tile next to the coordinate, with the direction to reach it
+    pub fn get_neighbours(&self, coor: (usize, usize)) -> Vec<(Direction, (usize, usize))> {
+        self.get_directions()
+            .iter()
+            .filter_map(|direction| Some((*direction, self.get_neighbour(coor, *direction)?)))
+            .collect()
+    }
+
+    /// Direction of a one step move, None if the tiles aren't next to each other
+    pub fn get_direction_between(&self, from: (usize, usize), to: (usize, usize)) -> Option<Direction> {
+        self.get_neighbours(from)
+            .into_iter()
+            .find(|(_, coor)| *coor == to)
+            .map(|(direction, _)| direction)
+    }
+
+    ///
+    /// Number of steps between two tiles on an empty board
+    /// (manhattan on `square`, king moves on `octile`, hex distance on `hex`),
+    /// the shortest way round on a torus
+    ///
+    pub fn get_distance(&self, a: (usize, usize), b: (usize, usize)) -> usize {
+        let size = self.height_width as i64;
+        let dx = b.0 as i64 - a.0 as i64;
+        let dy = b.1 as i64 - a.1 as i64;
+        let shifts: &[i64] = if self.wrap { &[-1, 0, 1] } else { &[0] };
+        let mut best = i64::MAX;
+        for sx in shifts {
+            for sy in shifts {
+                let (dx, dy) = (dx + sx * size, dy + sy * size);
+                let distance = match self.grid {
+                    GridKind::Square => dx.abs() + dy.abs(),
+                    GridKind::Octile => dx.abs().max(dy.abs()),
+                    GridKind::Hex => (dx.abs() + dy.abs() + (dx + dy).abs()) / 2,
+                };
+                best = best.min(distance);
+            }
+        }
+        best as usize
+    }
+
+    /// Spaces printed before a row of the board, hex rows are shifted half a tile
+    pub fn get_row_indent(&self, y: usize) -> String {
+        match self.grid {
+            GridKind::Hex => " ".repeat(y),
+            _ => String::new(),
+        }
+    }
+
+    /// Short description of the board printed with the state, None for the plain square
+    pub fn get_description(&self) -> Option<String> {
+        let grid = match self.grid {
+            GridKind::Square => "square grid",
+            GridKind::Octile => "8-direction grid",
+            GridKind::Hex => "hex grid",
+        };
+        match (self.grid, self.wrap) {
+            (GridKind::Square, false) => None,
+            (_, false) => Some(grid.to_string()),
+            (_, true) => Some(format!("{}, edges wrap around", grid)),
+        }
+    }
+}
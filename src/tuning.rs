@@ -0,0 +1,151 @@
+//! Tuning Module
+//!
+//! Tune the evaluation weights with SPSA
+//! (Simultaneous Perturbation Stochastic Approximation).
+//!
+//! Every iteration perturbs all the weights at once in a random direction,
+//! plays minimax matches between `weights + perturbation` and
+//! `weights - perturbation` with sides swapped on the same maps,
+//! and moves the weights toward the side that scored better.
+//!
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::agent::base::Agent;
+use crate::agent::search_agent::MinimaxAgent;
+use crate::game_loop::play_headless_game;
+use crate::rules::RuleSet;
+use crate::search::evaluation::EvalWeights;
+use crate::state_repr::{GameState, MapRandom};
+use crate::terminal::get_result_for_player;
+
+/// Settings of the tuning
+#[derive(Debug, Clone)]
+pub struct TuningConfig {
+    pub iterations: usize,
+    /// Maps played per iteration, each one twice with sides swapped
+    pub games_per_iteration: usize,
+    pub search_depth: u8,
+    pub rules: RuleSet,
+    /// Relative size of a weight update
+    pub step_size: f32,
+    /// Relative size of the perturbation
+    pub perturbation: f32,
+    pub seed: u64,
+    pub initial_weights: EvalWeights,
+}
+
+impl Default for TuningConfig {
+    fn default() -> Self {
+        TuningConfig {
+            iterations: 50,
+            games_per_iteration: 10,
+            search_depth: 3,
+            rules: RuleSet::with_board_size(5),
+            step_size: 0.1,
+            perturbation: 0.2,
+            seed: 0,
+            initial_weights: EvalWeights::default(),
+        }
+    }
+}
+
+/// Play matches between two sets of weights
+///
+/// # Parameters
+///
+/// * first, second: Weights of both sides
+/// * map_seeds: Maps to play, each one twice with sides swapped
+/// * config: Tuning settings (depth and board size)
+///
+/// # Returns
+///
+/// Average result of `first` between -1 (lost everything) and 1 (won everything)
+pub fn play_weights_match(
+    first: &EvalWeights,
+    second: &EvalWeights,
+    map_seeds: &[u64],
+    config: &TuningConfig,
+) -> f32 {
+    let mut total = 0;
+    for &map_seed in map_seeds {
+        let state = GameState::new_with_rules(&config.rules, MapRandom::FullyRandom, map_seed);
+        for first_is_player_one in [true, false] {
+            let mut first_agent = MinimaxAgent::new(config.search_depth, *first);
+            let mut second_agent = MinimaxAgent::new(config.search_depth, *second);
+            let mut agents: [&mut dyn Agent; 2] = if first_is_player_one {
+                [&mut first_agent, &mut second_agent]
+            } else {
+                [&mut second_agent, &mut first_agent]
+            };
+            let (final_state, _) = play_headless_game(&state, &mut agents);
+            total += get_result_for_player(&final_state, u8::from(!first_is_player_one));
+        }
+    }
+    f32::from(total) / (2 * map_seeds.len().max(1)) as f32
+}
+
+/// Where the tuning is after an iteration
+#[derive(Debug, Clone)]
+pub struct TuningProgress {
+    /// Iterations done, from 1
+    pub iteration: usize,
+    /// Score of `weights + perturbation` against `weights - perturbation`, -1 to 1
+    pub result: f32,
+    /// Weights after the iteration
+    pub weights: EvalWeights,
+}
+
+/// Tune the weights
+///
+/// # Parameters
+///
+/// * config: Tuning settings
+/// * on_iteration: called after every iteration with the progress
+///
+/// # Returns
+///
+/// The tuned weights
+pub fn tune_eval_weights(
+    config: &TuningConfig,
+    mut on_iteration: impl FnMut(&TuningProgress),
+) -> EvalWeights {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut theta = config.initial_weights.to_vec();
+
+    for iteration in 0..config.iterations {
+        // usual SPSA decay of the gains
+        let k = (iteration + 1) as f32;
+        let step_size = config.step_size / k.powf(0.602);
+        let perturbation = config.perturbation / k.powf(0.101);
+
+        // weights have different magnitudes, perturb them relatively
+        let scales: Vec<f32> = theta.iter().map(|w| w.abs().max(1.0)).collect();
+        let delta: Vec<f32> = theta
+            .iter()
+            .map(|_| if rng.gen::<bool>() { 1.0 } else { -1.0 })
+            .collect();
+        let shifted = |sign: f32| -> Vec<f32> {
+            (0..theta.len())
+                .map(|i| theta[i] + sign * perturbation * scales[i] * delta[i])
+                .collect()
+        };
+        let plus = EvalWeights::from_slice(&shifted(1.0));
+        let minus = EvalWeights::from_slice(&shifted(-1.0));
+
+        let first_seed = config.seed + (iteration * config.games_per_iteration) as u64;
+        let map_seeds: Vec<u64> =
+            (first_seed..first_seed + config.games_per_iteration as u64).collect();
+        let result = play_weights_match(&plus, &minus, &map_seeds, config);
+
+        for i in 0..theta.len() {
+            theta[i] += step_size * scales[i] * result * delta[i];
+        }
+        on_iteration(&TuningProgress {
+            iteration: iteration + 1,
+            result,
+            weights: EvalWeights::from_slice(&theta),
+        });
+    }
+    EvalWeights::from_slice(&theta)
+}